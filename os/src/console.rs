@@ -1,8 +1,11 @@
-use crate::sbi::console_putchar;
+use crate::{sbi::console_putchar, sync::spin::SpinLock};
 use core::fmt::{self, Write};
 
 struct Stdout;
 
+// 多个hart同时输出时不至于交错在一起
+static STDOUT: SpinLock<Stdout> = SpinLock::new(Stdout);
+
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         for c in s.bytes() {
@@ -13,7 +16,7 @@ impl Write for Stdout {
 }
 
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    STDOUT.lock().write_fmt(args).unwrap();
 }
 
 #[macro_export]
@@ -7,6 +7,8 @@ pub const APP_SIZE_LIMIT: usize = 0x2_0000;
 pub const CLOCK_FREQ: usize = 1250_0000;
 pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
 pub const MEMORY_END: usize = 0x8100_0000;
+// 与entry.asm中启动栈的数量保持一致
+pub const MAX_HARTS: usize = 4;
 
 pub const PTE_SIZE: usize = 8;
 pub const PTES_NUM: usize = PAGE_SIZE / PTE_SIZE;
@@ -33,8 +35,28 @@ pub const TRAP_CONTEXT_VA: VirtAddr = VirtAddr(TRAP_CONTEXT_VPN.0 << PAGE_SIZE_B
 
 pub const KERNEL_STACK_SIZE_BY_PAGE: usize = 2;
 pub const KERNEL_STACK_SIZE: usize = PAGE_SIZE * KERNEL_STACK_SIZE_BY_PAGE;
+// 主线程用户栈的初始大小, 之后随缺页向下增长
 pub const USER_STACK_SIZE_BY_PAGE: usize = 2;
 pub const USER_STACK_SIZE: usize = PAGE_SIZE * USER_STACK_SIZE_BY_PAGE;
+// thread_create创建的线程的用户栈从这里按tid向下排布, 每个栈下面留一个保护页
+pub const THREAD_STACK_TOP: usize = 0x40_0000_0000;
+// 位置无关的可执行文件加载到这里
+pub const PIE_BASE: usize = 0x4000_0000;
+// mmap只能映射到[MMAP_BASE, MMAP_TOP)中
+pub const MMAP_BASE: usize = 0x10_0000_0000;
+pub const MMAP_TOP: usize = 0x20_0000_0000;
+// 主线程的用户栈从这里向下增长, 最多增长到栈的rlimit, 和mmap区域之间至少隔一个保护页
+pub const USER_STACK_TOP: usize = 0x30_0000_0000;
+// 信号处理函数返回到这一页上的sigreturn跳板, 在主线程用户栈的正上方
+pub const SIGRETURN_VA: usize = USER_STACK_TOP;
+pub const DEFAULT_STACK_LIMIT: usize = 8 * 1024 * 1024;
+// exec时参数和环境变量在栈上最多占用栈rlimit的1/4, 但至少允许这么多
+pub const ARG_MAX: usize = 32 * PAGE_SIZE;
+// 参数和环境变量要先复制到内核堆上, 不管栈的rlimit有多大, 最多只能占用这么多
+pub const ARG_MAX_LIMIT: usize = 64 * PAGE_SIZE;
+pub const MAX_STACK_LIMIT: usize = USER_STACK_TOP - MMAP_TOP - PAGE_SIZE;
+// 从用户态读入的路径的最大长度, 包括结尾的0
+pub const PATH_MAX: usize = 4096;
 
 pub const VIRTIO0: (usize, usize) = (0x1000_1000, 0x1000);
 pub const MMIO: &[(usize, usize)] = &[VIRTIO0];
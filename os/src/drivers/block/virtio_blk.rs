@@ -8,17 +8,17 @@ use crate::{
         mem_set::kernel_token,
         page_table::TopLevelEntry,
     },
-    sync::up::UPSafeCell,
+    sync::spin::SpinLock,
 };
 
 const VIRTIO0: usize = 0x1000_1000;
 
-pub struct VirtIOBlock(UPSafeCell<VirtIOBlk<'static, VirtioHal>>);
+pub struct VirtIOBlock(SpinLock<VirtIOBlk<'static, VirtioHal>>);
 
 impl VirtIOBlock {
     pub fn new() -> Self {
         unsafe {
-            Self(UPSafeCell::new(
+            Self(SpinLock::new(
                 VirtIOBlk::<VirtioHal>::new(&mut *(VIRTIO0 as *mut VirtIOHeader))
                     .expect("virtio_blk: failed to init virtio_blk"),
             ))
@@ -29,14 +29,14 @@ impl VirtIOBlock {
 impl BlockDevice for VirtIOBlock {
     fn read_block(&self, block_addr: BlockAddr, buf: &mut [u8]) {
         self.0
-            .exclusive_access()
+            .lock()
             .read_block(block_addr as usize, buf)
             .expect("virtio_blk: read_block failed")
     }
 
     fn write_block(&self, block_addr: BlockAddr, buf: &[u8]) {
         self.0
-            .exclusive_access()
+            .lock()
             .write_block(block_addr as usize, buf)
             .expect("virtio_blk: write_block failed")
     }
@@ -48,7 +48,7 @@ impl Hal for VirtioHal {
     fn dma_alloc(pages: usize) -> virtio_drivers::PhysAddr {
         let mut base = PhysPageNum(0);
         for i in 0..pages {
-            let frame = ALLOCATOR.exclusive_access().alloc();
+            let frame = ALLOCATOR.lock().alloc();
             if i == 0 {
                 base = frame;
             }
@@ -59,7 +59,7 @@ impl Hal for VirtioHal {
 
     fn dma_dealloc(paddr: virtio_drivers::PhysAddr, pages: usize) -> i32 {
         let base = PhysAddr(paddr).phys_page_num();
-        (0..pages).for_each(|i| ALLOCATOR.exclusive_access().dealloc(base + i));
+        (0..pages).for_each(|i| ALLOCATOR.lock().dealloc(base + i));
         0
     }
 
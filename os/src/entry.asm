@@ -1,12 +1,26 @@
-.section .text.entry
+    .section .text.entry
     .globl _start
 _start:
-    la sp, boot_stack_top
-    call rust_main
+    la t2, rust_main
+    j __boot
+    .globl _start_secondary
+_start_secondary:
+    la t2, rust_main_secondary
+__boot:
+    # a0 = hartid, 之后一直保存在tp中
+    mv tp, a0
+    # 每个hart使用各自的启动栈
+    addi t0, a0, 1
+    li t1, 4096 * 16
+    mul t0, t0, t1
+    la sp, boot_stack_lower_bound
+    add sp, sp, t0
+    jr t2
 
     .section .bss.stack
     .globl boot_stack_lower_bound
 boot_stack_lower_bound:
-    .space 4096 * 16
+    # 与constant::MAX_HARTS保持一致
+    .space 4096 * 16 * 4
     .globl boot_stack_top
 boot_stack_top:
@@ -2,8 +2,8 @@ use super::File;
 use crate::drivers::block::BLOCK_DEVICE;
 use crate::fs::SeekType;
 use crate::mm::address::UserBuffer;
-use crate::sync::up::UPSafeCell;
-use crate::syscall::{SEEK_OUT_OF_RANGE, UNREADABLE, UNWRITABLE};
+use crate::sync::spin::SpinLock;
+use crate::syscall::{Errno, SyscallResult};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use bitflags::bitflags;
@@ -13,15 +13,15 @@ use yfs::yfs::YeFs;
 
 pub struct OSInode {
     flags: OSInodeFlags,
-    inner: UPSafeCell<OSInodeInner>,
+    inner: SpinLock<OSInodeInner>,
 }
 
 impl File for OSInode {
-    fn read(&self, buf: UserBuffer) -> isize {
+    fn read(&self, buf: UserBuffer) -> SyscallResult {
         if !self.readable() {
-            return UNREADABLE;
+            return Err(Errno::EBADF);
         }
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         let mut total = 0u32;
         for buf in buf {
             let read = inner.inode.read(inner.offset, buf);
@@ -31,14 +31,15 @@ impl File for OSInode {
             inner.offset += read;
             total += read;
         }
-        total as isize
+        Ok(total as usize)
     }
 
-    fn write(&self, buf: UserBuffer) -> isize {
+    fn write(&self, buf: UserBuffer) -> SyscallResult {
         if !self.writable() {
-            return UNWRITABLE;
+            return Err(Errno::EBADF);
         }
-        let mut inner = self.inner.exclusive_access();
+        let len = buf.len();
+        let mut inner = self.inner.lock();
         let mut total = 0u32;
         for buf in buf {
             let write = inner.inode.write(inner.offset, buf);
@@ -48,7 +49,12 @@ impl File for OSInode {
             inner.offset += write;
             total += write;
         }
-        total as isize
+        //一个字节都没能写入, 说明磁盘空间不足
+        if total == 0 && len != 0 {
+            Err(Errno::ENOSPC)
+        } else {
+            Ok(total as usize)
+        }
     }
 
     fn readable(&self) -> bool {
@@ -63,32 +69,36 @@ impl File for OSInode {
         true
     }
 
-    fn seek(&self, ty: super::SeekType, offset: i32) -> isize {
-        let mut inner = self.inner.exclusive_access();
+    fn seek(&self, ty: super::SeekType, offset: i32) -> SyscallResult {
+        let mut inner = self.inner.lock();
         let to = match ty {
             super::SeekType::Set => offset,
             super::SeekType::Cur => inner.offset as i32 + offset,
             super::SeekType::End => inner.inode.size() as i32 + offset,
         };
         if to < 0 || to > inner.inode.size() as i32 {
-            SEEK_OUT_OF_RANGE
+            Err(Errno::EINVAL)
         } else {
             inner.offset = to as u32;
-            to as isize
+            Ok(to as usize)
         }
     }
+
+    fn inode(&self) -> Option<Arc<Vnode>> {
+        Some(self.inner.lock().inode.clone())
+    }
 }
 
 impl OSInode {
     fn new(flags: OSInodeFlags, inode: Arc<Vnode>) -> Self {
         Self {
             flags,
-            inner: unsafe { UPSafeCell::new(OSInodeInner { offset: 0, inode }) },
+            inner: SpinLock::new(OSInodeInner { offset: 0, inode }),
         }
     }
 
     pub fn read_all(&self) -> Vec<u8> {
-        let mut inner = self.inner.exclusive_access();
+        let mut inner = self.inner.lock();
         let mut buf = [0u8; 512];
         let mut ret = Vec::new();
         loop {
@@ -102,22 +112,25 @@ impl OSInode {
         ret
     }
 
-    pub fn open(name: &str, flags: OpenFlags) -> Option<Arc<Self>> {
-        let inode = ROOT.dir_find(name).or_else(|| {
-            if flags.contains(OpenFlags::CREATE) {
-                Some(ROOT.create(name).unwrap())
-            } else {
-                None
-            }
-        })?;
+    pub fn open(name: &str, flags: OpenFlags) -> Result<Arc<Self>, Errno> {
+        let inode = ROOT.dir_find(name).map_or_else(
+            || {
+                if flags.contains(OpenFlags::CREATE) {
+                    ROOT.create(name).map_err(|_| Errno::EEXIST)
+                } else {
+                    Err(Errno::ENOENT)
+                }
+            },
+            Ok,
+        )?;
         if flags.contains(OpenFlags::TRUNC) {
             inode.modify_inode(|inode| inode.clear(&YFS.data_allocator, &YFS.device));
         };
         let inode = OSInode::new(flags.into(), inode);
         if flags.contains(OpenFlags::APPEND) {
-            inode.seek(SeekType::End, 0);
+            inode.seek(SeekType::End, 0)?;
         }
-        Some(Arc::new(inode))
+        Ok(Arc::new(inode))
     }
 }
 
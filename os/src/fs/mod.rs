@@ -5,8 +5,10 @@ pub mod stdio;
 pub mod zero;
 use crate::{
     mm::address::UserBuffer,
-    syscall::{UNREADABLE, UNSEEKABLE, UNWRITABLE},
+    syscall::{Errno, SyscallResult},
 };
+use alloc::sync::Arc;
+use yfs::vfs::Vnode;
 
 pub trait File: Send + Sync {
     fn readable(&self) -> bool {
@@ -18,14 +20,18 @@ pub trait File: Send + Sync {
     fn seekable(&self) -> bool {
         false
     }
-    fn read(&self, _: UserBuffer) -> isize {
-        UNREADABLE
+    fn read(&self, _: UserBuffer) -> SyscallResult {
+        Err(Errno::EBADF)
     }
-    fn write(&self, _: UserBuffer) -> isize {
-        UNWRITABLE
+    fn write(&self, _: UserBuffer) -> SyscallResult {
+        Err(Errno::EBADF)
     }
-    fn seek(&self, _: SeekType, _: i32) -> isize {
-        UNSEEKABLE
+    fn seek(&self, _: SeekType, _: i32) -> SyscallResult {
+        Err(Errno::ESPIPE)
+    }
+    //能被mmap的文件返回它的inode
+    fn inode(&self) -> Option<Arc<Vnode>> {
+        None
     }
 }
 
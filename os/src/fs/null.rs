@@ -1,6 +1,7 @@
 use alloc::sync::Arc;
 
 use super::File;
+use crate::syscall::SyscallResult;
 
 struct Null;
 
@@ -17,16 +18,16 @@ impl File for Null {
         true
     }
 
-    fn read(&self, buf: crate::mm::address::UserBuffer) -> isize {
-        buf.len() as isize
+    fn read(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
+        Ok(buf.len())
     }
 
-    fn write(&self, buf: crate::mm::address::UserBuffer) -> isize {
-        buf.len() as isize
+    fn write(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
+        Ok(buf.len())
     }
 
-    fn seek(&self, _: super::SeekType, _: i32) -> isize {
-        0
+    fn seek(&self, _: super::SeekType, _: i32) -> SyscallResult {
+        Ok(0)
     }
 }
 
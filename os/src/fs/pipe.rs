@@ -1,8 +1,10 @@
 use alloc::sync::{Arc, Weak};
 
 use crate::{
-    mm::address::UserBuffer, process::processor::PROCESSOR, sync::up::UPSafeCell,
-    syscall::PIPE_READER_CLOSED,
+    mm::address::UserBuffer,
+    process::{processor::PROCESSOR, signal::SignalFlags},
+    sync::spin::SpinLock,
+    syscall::{Errno, SyscallResult},
 };
 
 use super::File;
@@ -28,17 +30,15 @@ struct Pipe {
 }
 
 impl Pipe {
-    fn new() -> Arc<UPSafeCell<Self>> {
-        unsafe {
-            Arc::new(UPSafeCell::new(Pipe {
-                buffer: [0; PIPE_SIZE],
-                head: 0,
-                tail: 0,
-                state: Empty,
-                write_end: None,
-                read_end: None,
-            }))
-        }
+    fn new() -> Arc<SpinLock<Self>> {
+        Arc::new(SpinLock::new(Pipe {
+            buffer: [0; PIPE_SIZE],
+            head: 0,
+            tail: 0,
+            state: Empty,
+            write_end: None,
+            read_end: None,
+        }))
     }
 
     fn set_write_end(&mut self, write_end: &Arc<PipeWriter>) -> &mut Self {
@@ -100,18 +100,18 @@ impl Pipe {
     }
 }
 
-struct PipeReader(Arc<UPSafeCell<Pipe>>);
+struct PipeReader(Arc<SpinLock<Pipe>>);
 
 impl PipeReader {
-    fn new(pipe: Arc<UPSafeCell<Pipe>>) -> Arc<Self> {
+    fn new(pipe: Arc<SpinLock<Pipe>>) -> Arc<Self> {
         Arc::new(Self(pipe))
     }
 }
 
-struct PipeWriter(Arc<UPSafeCell<Pipe>>);
+struct PipeWriter(Arc<SpinLock<Pipe>>);
 
 impl PipeWriter {
-    fn new(pipe: Arc<UPSafeCell<Pipe>>) -> Arc<Self> {
+    fn new(pipe: Arc<SpinLock<Pipe>>) -> Arc<Self> {
         Arc::new(Self(pipe))
     }
 }
@@ -121,18 +121,22 @@ impl File for PipeReader {
         true
     }
 
-    fn read(&self, user_buf: UserBuffer) -> isize {
+    fn read(&self, user_buf: UserBuffer) -> SyscallResult {
         let to_read = user_buf.len();
         let mut read = 0usize;
         let mut it = user_buf.flat_map(|buf| buf.iter_mut());
         loop {
-            let pipe = self.0.exclusive_access();
+            let mut pipe = self.0.lock();
             let this_read = pipe.available_to_read();
             if this_read == 0 {
                 if pipe.is_writer_closed() {
-                    return read as isize;
+                    return Ok(read);
                 }
                 drop(pipe);
+                //进程正在退出, 不再等待
+                if PROCESSOR.exclusive_access().current().unwrap().exiting {
+                    return Err(Errno::EINTR);
+                }
                 PROCESSOR.exclusive_access().suspend_current().schedule();
                 continue;
             }
@@ -141,10 +145,10 @@ impl File for PipeReader {
                     *byte = pipe.read_byte();
                     read += 1;
                     if read == to_read {
-                        return read as isize;
+                        return Ok(read);
                     }
                 } else {
-                    return read as isize;
+                    return Ok(read);
                 }
             }
         }
@@ -155,18 +159,34 @@ impl File for PipeWriter {
     fn writable(&self) -> bool {
         true
     }
-    fn write(&self, user_buf: UserBuffer) -> isize {
+    fn write(&self, user_buf: UserBuffer) -> SyscallResult {
         let to_write = user_buf.len();
         let mut write = 0usize;
         let mut it = user_buf.flat_map(|buf| buf.iter_mut());
         loop {
-            let pipe = self.0.exclusive_access();
+            let mut pipe = self.0.lock();
             if pipe.is_reader_closed() {
-                return PIPE_READER_CLOSED;
+                drop(pipe);
+                //和linux一样向写者发送SIGPIPE, 默认动作是终止进程
+                //信号被忽略或者有处理函数时返回EPIPE, 已经写入的部分照常返回
+                PROCESSOR
+                    .exclusive_access()
+                    .current()
+                    .unwrap()
+                    .raise(SignalFlags::SIGPIPE);
+                return if write > 0 {
+                    Ok(write)
+                } else {
+                    Err(Errno::EPIPE)
+                };
             }
             let this_write = pipe.available_to_write();
             if this_write == 0 {
                 drop(pipe);
+                //进程正在退出, 不再等待
+                if PROCESSOR.exclusive_access().current().unwrap().exiting {
+                    return Err(Errno::EINTR);
+                }
                 PROCESSOR.exclusive_access().suspend_current().schedule();
                 continue;
             }
@@ -175,10 +195,10 @@ impl File for PipeWriter {
                     pipe.write_byte(byte);
                     write += 1;
                     if write == to_write {
-                        return write as isize;
+                        return Ok(write);
                     }
                 } else {
-                    return write as isize;
+                    return Ok(write);
                 }
             }
         }
@@ -190,8 +210,6 @@ pub fn make_pipe() -> (Arc<dyn File + Send + Sync>, Arc<dyn File + Send + Sync>)
     let pipe = Pipe::new();
     let reader = PipeReader::new(pipe.clone());
     let writer = PipeWriter::new(pipe.clone());
-    pipe.exclusive_access()
-        .set_read_end(&reader)
-        .set_write_end(&writer);
+    pipe.lock().set_read_end(&reader).set_write_end(&writer);
     (reader, writer)
 }
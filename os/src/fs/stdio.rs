@@ -1,6 +1,10 @@
 use alloc::sync::Arc;
 
-use crate::{process::processor::PROCESSOR, sbi::console_getchar};
+use crate::{
+    process::processor::PROCESSOR,
+    sbi::console_getchar,
+    syscall::{Errno, SyscallResult},
+};
 
 use super::File;
 
@@ -17,12 +21,16 @@ impl File for Stdin {
         true
     }
 
-    fn read(&self, mut user_buf: crate::mm::address::UserBuffer) -> isize {
+    fn read(&self, mut user_buf: crate::mm::address::UserBuffer) -> SyscallResult {
         assert!(user_buf.len() == 1);
         let mut c: usize;
         loop {
             c = console_getchar();
             if c == 0 {
+                //进程正在退出, 不再等待
+                if PROCESSOR.exclusive_access().current().unwrap().exiting {
+                    return Err(Errno::EINTR);
+                }
                 PROCESSOR.exclusive_access().suspend_current().schedule();
                 continue;
             } else {
@@ -30,7 +38,7 @@ impl File for Stdin {
             }
         }
         *user_buf.next().unwrap().first_mut().unwrap() = c as u8;
-        1
+        Ok(1)
     }
 }
 
@@ -39,13 +47,13 @@ impl File for Stdout {
         true
     }
 
-    fn write(&self, buf: crate::mm::address::UserBuffer) -> isize {
+    fn write(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
         let len = buf.len();
         for buf in buf {
             let s = unsafe { core::str::from_utf8_unchecked(buf) };
             print!("{}", s);
         }
-        len as isize
+        Ok(len)
     }
 }
 
@@ -54,13 +62,13 @@ impl File for Stderr {
         true
     }
 
-    fn write(&self, buf: crate::mm::address::UserBuffer) -> isize {
+    fn write(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
         let len = buf.len();
         for buf in buf {
             let s = unsafe { core::str::from_utf8_unchecked(buf) };
             print!("{}", s);
         }
-        len as isize
+        Ok(len)
     }
 }
 
@@ -1,6 +1,7 @@
 use alloc::sync::Arc;
 
 use super::File;
+use crate::syscall::SyscallResult;
 
 struct Zero;
 
@@ -17,20 +18,20 @@ impl File for Zero {
         true
     }
 
-    fn read(&self, buf: crate::mm::address::UserBuffer) -> isize {
+    fn read(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
         let len = buf.len();
         for buf in buf {
             buf.fill(0);
         }
-        len as isize
+        Ok(len)
     }
 
-    fn write(&self, buf: crate::mm::address::UserBuffer) -> isize {
-        buf.len() as isize
+    fn write(&self, buf: crate::mm::address::UserBuffer) -> SyscallResult {
+        Ok(buf.len())
     }
 
-    fn seek(&self, _: super::SeekType, _: i32) -> isize {
-        0
+    fn seek(&self, _: super::SeekType, _: i32) -> SyscallResult {
+        Ok(0)
     }
 }
 
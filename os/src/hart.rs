@@ -0,0 +1,26 @@
+use core::arch::asm;
+
+use log::{info, warn};
+
+use crate::{constant::MAX_HARTS, sbi::hart_start};
+
+// 进入内核时tp中保存的是当前hart的id
+pub fn hart_id() -> usize {
+    let id;
+    unsafe { asm!("mv {}, tp", out(reg) id) };
+    id
+}
+
+// 由启动hart调用, 通过SBI HSM唤醒其余的hart
+pub fn start_others(boot_hart: usize) {
+    extern "C" {
+        fn _start_secondary();
+    }
+    for id in (0..MAX_HARTS).filter(|&id| id != boot_hart) {
+        if hart_start(id, _start_secondary as usize, 0) {
+            info!("[kernel] start hart {}", id);
+        } else {
+            warn!("[kernel] failed to start hart {}", id);
+        }
+    }
+}
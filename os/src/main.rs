@@ -14,6 +14,7 @@ mod console;
 mod constant;
 pub mod drivers;
 pub mod fs;
+mod hart;
 mod lang_items;
 mod logging;
 mod mm;
@@ -36,16 +37,26 @@ use log::*;
 global_asm!(include_str!("entry.asm"));
 
 #[no_mangle]
-pub fn rust_main() -> ! {
+pub fn rust_main(hartid: usize) -> ! {
     init();
     info!("[kernel] Welcome to TroodontidaeOS!");
-    QUEUE
-        .exclusive_access()
-        .push(INITPROC.exclusive_access() as *mut _);
-    task_insert(
-        INITPROC.exclusive_access().pid,
-        INITPROC.exclusive_access() as *mut _,
-    );
+    let initproc = INITPROC.get();
+    QUEUE.lock().push(initproc.thread(0).unwrap());
+    task_insert(initproc.pid, initproc);
+    hart::start_others(hartid);
+    PROCESSOR.exclusive_access().run_tasks();
+    shutdown(false);
+}
+
+// 其余的hart由启动hart通过SBI唤醒, 共享的数据结构此时都已经初始化好了
+#[no_mangle]
+pub fn rust_main_secondary(hartid: usize) -> ! {
+    unsafe {
+        mm::activate();
+        trap::init();
+        timer::init();
+    }
+    info!("[kernel] hart {} is online", hartid);
     PROCESSOR.exclusive_access().run_tasks();
     shutdown(false);
 }
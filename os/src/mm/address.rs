@@ -9,7 +9,12 @@ use crate::constant::{
     VA_MASK, VA_WIDTH, VPN_MASK, VPN_WIDTH,
 };
 
-use super::page_table::{PageTableEntry, TopLevelEntry};
+use crate::syscall::SyscallResult;
+
+use super::{
+    page_table::{PageTableEntry, TopLevelEntry},
+    uaccess::{check_user_range, user_page},
+};
 
 //56位 符号拓展
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -604,14 +609,30 @@ pub trait Reader<T> {
 pub struct UserBuffer {
     span: VirtAddrSpan,
     page_table_entry: TopLevelEntry,
+    //缓冲区会被内核写入, 需要写权限
+    write: bool,
 }
 
 impl UserBuffer {
-    pub fn new(span: impl Into<VirtAddrSpan>, page_table_entry: TopLevelEntry) -> Self {
-        Self {
-            span: span.into(),
-            page_table_entry,
+    //构造时检查每一页都可以访问, 不合法时返回EFAULT
+    pub fn new(
+        start: usize,
+        len: usize,
+        page_table_entry: TopLevelEntry,
+        write: bool,
+    ) -> SyscallResult<Self> {
+        check_user_range(start, len)?;
+        let span = VirtAddrSpan::from(VirtAddr(start)..VirtAddr(start + len));
+        if len != 0 {
+            for vpn in VirtPageSpan::new(span.start.floor()..span.end.ceil()) {
+                user_page(page_table_entry, vpn, write)?;
+            }
         }
+        Ok(Self {
+            span,
+            page_table_entry,
+            write,
+        })
     }
 
     pub fn len(&self) -> usize {
@@ -634,7 +655,8 @@ impl Iterator for UserBuffer {
             } else {
                 PAGE_SIZE
             };
-            let ppn = self.page_table_entry.translate(start_page).unwrap().ppn();
+            //阻塞期间其他线程可能解除了映射, 此时提前结束
+            let ppn = user_page(self.page_table_entry, start_page, self.write).ok()?;
             return Some(&mut ppn.read_as_bytes_array()[slice_begin..slice_end]);
         } else {
             None
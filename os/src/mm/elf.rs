@@ -0,0 +1,246 @@
+//! 加载前检查ELF文件, 不合法时返回ENOEXEC, 此时还没有分配任何内存
+//! 只支持静态链接的RISC-V 64位可执行文件, 位置无关的可执行文件会被加载到PIE_BASE并做重定位
+
+use alloc::vec::Vec;
+use xmas_elf::{
+    program::{ProgramHeader, Type},
+    ElfFile,
+};
+
+use crate::{
+    constant::{MMAP_BASE, PAGE_SIZE, PIE_BASE},
+    syscall::{Errno, SyscallResult},
+};
+
+use super::{address::VirtAddr, virt_mem_area::Permission};
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const EM_RISCV: u16 = 243;
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+
+const DT_NULL: u64 = 0;
+const DT_RELA: u64 = 7;
+const DT_RELASZ: u64 = 8;
+const DT_RELAENT: u64 = 9;
+const DYN_SIZE: usize = 16;
+const RELA_SIZE: usize = 24;
+const R_RISCV_NONE: u64 = 0;
+const R_RISCV_RELATIVE: u64 = 3;
+
+// 一个需要加载的段, 地址已经加上了加载基址
+pub struct Segment<'a> {
+    pub start: usize,
+    pub end: usize,
+    // 文件中的内容, 比段短的部分(.bss)是0
+    pub data: &'a [u8],
+    pub perm: Permission,
+    // 在文件中的虚拟地址, 用来查找重定位表
+    vaddr: usize,
+}
+
+pub struct ElfImage<'a> {
+    pub entry: usize,
+    pub segments: Vec<Segment<'a>>,
+    // 加载后要在这些地址写入重定位后的值
+    pub relocations: Vec<(usize, u64)>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> SyscallResult<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(Errno::ENOEXEC)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> SyscallResult<u64> {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(data.get(offset..offset + 8).ok_or(Errno::ENOEXEC)?);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// 取出[offset, offset + len), 越界时返回ENOEXEC
+fn slice(data: &[u8], offset: usize, len: usize) -> SyscallResult<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(Errno::ENOEXEC)
+}
+
+fn load_segment<'a>(
+    data: &'a [u8],
+    ph: &ProgramHeader,
+    base: usize,
+) -> SyscallResult<Option<Segment<'a>>> {
+    let vaddr = ph.virtual_addr() as usize;
+    let mem_size = ph.mem_size() as usize;
+    let file_size = ph.file_size() as usize;
+    let offset = ph.offset() as usize;
+    let align = ph.align() as usize;
+    if file_size > mem_size {
+        return Err(Errno::ENOEXEC);
+    }
+    let file = slice(data, offset, file_size)?;
+    if align > 1 && (!align.is_power_of_two() || vaddr % align != offset % align) {
+        return Err(Errno::ENOEXEC);
+    }
+    if mem_size == 0 {
+        return Ok(None);
+    }
+    //段只能放在空页之上, mmap区域之下
+    let start = base.checked_add(vaddr).ok_or(Errno::ENOEXEC)?;
+    let end = start.checked_add(mem_size).ok_or(Errno::ENOEXEC)?;
+    if start < PAGE_SIZE || end > MMAP_BASE {
+        return Err(Errno::ENOEXEC);
+    }
+    let mut perm = Permission::U;
+    let flags = ph.flags();
+    if flags.is_read() {
+        perm |= Permission::R;
+    }
+    if flags.is_write() {
+        perm |= Permission::W;
+    }
+    if flags.is_execute() {
+        perm |= Permission::X;
+    }
+    Ok(Some(Segment {
+        start,
+        end,
+        data: file,
+        perm,
+        vaddr,
+    }))
+}
+
+// 在已加载的段中找到虚拟地址addr处长为len的内容
+fn segment_bytes<'a>(segments: &[Segment<'a>], addr: usize, len: usize) -> SyscallResult<&'a [u8]> {
+    segments
+        .iter()
+        .find(|seg| seg.vaddr <= addr && addr < seg.vaddr + seg.data.len())
+        .ok_or(Errno::ENOEXEC)
+        .and_then(|seg| slice(seg.data, addr - seg.vaddr, len))
+}
+
+// 只支持R_RISCV_RELATIVE, 静态链接的PIE只会用到它
+fn relocations(
+    data: &[u8],
+    dynamic: &[u8],
+    segments: &[Segment],
+    base: usize,
+) -> SyscallResult<Vec<(usize, u64)>> {
+    let (mut rela, mut rela_size, mut rela_ent) = (None, 0, RELA_SIZE);
+    for entry in dynamic.chunks_exact(DYN_SIZE) {
+        let tag = read_u64(entry, 0)?;
+        let val = read_u64(entry, 8)? as usize;
+        match tag {
+            DT_NULL => break,
+            DT_RELA => rela = Some(val),
+            DT_RELASZ => rela_size = val,
+            DT_RELAENT => rela_ent = val,
+            _ => {}
+        }
+    }
+    let rela = match rela {
+        Some(rela) => rela,
+        None => return Ok(Vec::new()),
+    };
+    if rela_ent != RELA_SIZE {
+        return Err(Errno::ENOEXEC);
+    }
+    let table = segment_bytes(segments, rela, rela_size)?;
+    let mut relocations = Vec::new();
+    for entry in table.chunks_exact(RELA_SIZE) {
+        let offset = read_u64(entry, 0)? as usize;
+        let info = read_u64(entry, 8)?;
+        let addend = read_u64(entry, 16)?;
+        match info & 0xffff_ffff {
+            R_RISCV_NONE => {}
+            R_RISCV_RELATIVE => {
+                let addr = base.checked_add(offset).ok_or(Errno::ENOEXEC)?;
+                if !segments
+                    .iter()
+                    .any(|seg| seg.start <= addr && addr + 8 <= seg.end)
+                {
+                    return Err(Errno::ENOEXEC);
+                }
+                relocations.push((addr, (base as u64).wrapping_add(addend)));
+            }
+            _ => return Err(Errno::ENOEXEC),
+        }
+    }
+    Ok(relocations)
+}
+
+pub fn parse(data: &[u8]) -> SyscallResult<ElfImage> {
+    if data.len() < EHDR_SIZE
+        || data[..4] != ELF_MAGIC
+        || data[4] != ELFCLASS64
+        || data[5] != ELFDATA2LSB
+        || read_u16(data, 18)? != EM_RISCV
+    {
+        return Err(Errno::ENOEXEC);
+    }
+    let pie = match read_u16(data, 16)? {
+        ET_EXEC => false,
+        ET_DYN => true,
+        _ => return Err(Errno::ENOEXEC),
+    };
+    //xmas_elf遇到越界的程序头会panic, 先自己检查
+    let ph_offset = read_u64(data, 32)? as usize;
+    let ph_size = read_u16(data, 54)? as usize;
+    let ph_count = read_u16(data, 56)?;
+    if ph_offset == 0 || ph_count == 0 || ph_size != PHDR_SIZE {
+        return Err(Errno::ENOEXEC);
+    }
+    slice(data, ph_offset, ph_count as usize * PHDR_SIZE)?;
+
+    let elf = ElfFile::new(data).map_err(|_| Errno::ENOEXEC)?;
+    let base = if pie { PIE_BASE } else { 0 };
+    let mut segments = Vec::new();
+    let mut dynamic = None;
+    for i in 0..ph_count {
+        let ph = elf.program_header(i).map_err(|_| Errno::ENOEXEC)?;
+        match ph.get_type() {
+            Ok(Type::Load) => segments.extend(load_segment(data, &ph, base)?),
+            Ok(Type::Dynamic) => {
+                dynamic = Some(slice(data, ph.offset() as usize, ph.file_size() as usize)?)
+            }
+            //没有动态链接器
+            Ok(Type::Interp) => return Err(Errno::ENOEXEC),
+            _ => {}
+        }
+    }
+    if segments.is_empty() {
+        return Err(Errno::ENOEXEC);
+    }
+    //不同的段不能落在同一页上
+    segments.sort_unstable_by_key(|seg| seg.start);
+    if segments
+        .windows(2)
+        .any(|pair| VirtAddr(pair[0].end).ceil() > VirtAddr(pair[1].start).floor())
+    {
+        return Err(Errno::ENOEXEC);
+    }
+    let entry = base
+        .checked_add(read_u64(data, 24)? as usize)
+        .ok_or(Errno::ENOEXEC)?;
+    if !segments
+        .iter()
+        .any(|seg| seg.perm.contains(Permission::X) && seg.start <= entry && entry < seg.end)
+    {
+        return Err(Errno::ENOEXEC);
+    }
+    let relocations = match dynamic {
+        Some(dynamic) if pie => relocations(data, dynamic, &segments, base)?,
+        _ => Vec::new(),
+    };
+    Ok(ElfImage {
+        entry,
+        segments,
+        relocations,
+    })
+}
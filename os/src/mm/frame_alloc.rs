@@ -1,14 +1,16 @@
 use super::address::PhysPageNum;
-use crate::{constant::MEMORY_END, mm::address::PhysAddr, sync::up::UPSafeCell};
-use alloc::collections::VecDeque;
+use crate::{constant::MEMORY_END, mm::address::PhysAddr, sync::spin::SpinLock};
+use alloc::collections::{BTreeMap, VecDeque};
 use log::info;
 
 pub struct FrameAllocator {
     pool: VecDeque<usize>,
+    //写时复制时被多个页表项共享的物理页的引用计数, 不在其中的页只有一个引用
+    shared: BTreeMap<usize, usize>,
 }
 
 lazy_static! {
-    pub static ref ALLOCATOR: UPSafeCell<FrameAllocator> = unsafe {
+    pub static ref ALLOCATOR: SpinLock<FrameAllocator> = {
         extern "C" {
             fn ekernel();
         }
@@ -16,7 +18,7 @@ lazy_static! {
         let start = PhysAddr(ekernel as usize).phys_page_num();
         let end = PhysAddr(MEMORY_END).phys_page_num();
         let inner = FrameAllocator::new(start, end);
-        UPSafeCell::new(inner)
+        SpinLock::new(inner)
     };
 }
 
@@ -24,6 +26,7 @@ impl FrameAllocator {
     fn new(PhysPageNum(l): PhysPageNum, PhysPageNum(r): PhysPageNum) -> Self {
         Self {
             pool: (l..r).collect(),
+            shared: BTreeMap::new(),
         }
     }
 
@@ -37,7 +40,24 @@ impl FrameAllocator {
         }
     }
 
+    //多一个页表项引用这个物理页
+    pub fn share(&mut self, PhysPageNum(p): PhysPageNum) {
+        *self.shared.entry(p).or_insert(1) += 1;
+    }
+
+    pub fn ref_count(&self, PhysPageNum(p): PhysPageNum) -> usize {
+        self.shared.get(&p).copied().unwrap_or(1)
+    }
+
+    //共享的物理页只减少引用计数, 最后一个引用释放时才回收
     pub fn dealloc(&mut self, PhysPageNum(p): PhysPageNum) {
+        if let Some(count) = self.shared.get_mut(&p) {
+            *count -= 1;
+            if *count == 1 {
+                self.shared.remove(&p);
+            }
+            return;
+        }
         if self.pool.iter().any(|&item| item == p) {
             panic!("dealloc a frame twice");
         } else {
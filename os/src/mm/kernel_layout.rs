@@ -12,6 +12,7 @@ mod symbol {
         pub fn ebss();
         pub fn ekernel();
         pub fn strampoline();
+        pub fn ssigreturn();
     }
 }
 
@@ -54,3 +55,7 @@ pub fn ekernel() -> PhysPageNum {
 pub fn strampoline() -> PhysPageNum {
     PhysAddr(symbol::strampoline as usize).phys_page_num()
 }
+
+pub fn ssigreturn() -> PhysPageNum {
+    PhysAddr(symbol::ssigreturn as usize).phys_page_num()
+}
@@ -1,43 +1,57 @@
 use crate::{
     constant::{KERNEL_STACK_SIZE_BY_PAGE, TRAMPOLINE_VPN},
     mm::mem_set::KERNEL_MEM_SPACE,
-    process::pid::Pid,
+    process::pid::Allocator,
+    sync::spin::SpinLock,
 };
 
 use super::address::{VirtAddr, VirtPageSpan};
 
-//内核栈的代理对象
-pub struct KernelStack;
+lazy_static! {
+    static ref ALLOCATOR: SpinLock<Allocator> = SpinLock::new(Allocator::new());
+}
+
+//内核栈的代理对象, 每个线程一个, drop时回收内核栈
+pub struct KernelStack(usize);
 
 impl KernelStack {
     // [top, bottom)
-    pub fn get_postion(pid: Pid) -> VirtPageSpan {
-        let offset = (KERNEL_STACK_SIZE_BY_PAGE + 1) * pid.0;
+    pub fn get_postion(id: usize) -> VirtPageSpan {
+        let offset = (KERNEL_STACK_SIZE_BY_PAGE + 1) * id;
         let top = TRAMPOLINE_VPN - offset - 2usize;
         let bottom = TRAMPOLINE_VPN - offset;
         (top..bottom).into()
     }
 
-    pub fn new(pid: Pid) -> Self {
+    pub fn new() -> Self {
         use crate::mm::virt_mem_area::Permission;
-        let range = Self::get_postion(pid);
+        let id = ALLOCATOR.lock().alloc();
+        let range = Self::get_postion(id);
         KERNEL_MEM_SPACE
-            .exclusive_access()
+            .lock()
             .insert_framed_area(range, Permission::W | Permission::R);
-        Self
+        Self(id)
     }
 
     #[allow(unused)]
-    pub fn push_on_btm<T>(&self, pid: Pid, val: T) -> VirtAddr
+    pub fn push_on_btm<T>(&self, val: T) -> VirtAddr
     where
         T: Sized + 'static,
     {
-        let ret = self.btm(pid) - core::mem::size_of::<T>();
+        let ret = self.btm() - core::mem::size_of::<T>();
         *ret.identical().as_mut() = val;
         ret
     }
 
-    pub fn btm(&self, pid: Pid) -> VirtAddr {
-        Self::get_postion(pid).end.floor()
+    pub fn btm(&self) -> VirtAddr {
+        Self::get_postion(self.0).end.floor()
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let range = Self::get_postion(self.0);
+        KERNEL_MEM_SPACE.lock().remove_area(range.start);
+        ALLOCATOR.lock().dealloc(self.0);
     }
 }
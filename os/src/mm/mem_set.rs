@@ -4,18 +4,23 @@ use core::{arch::asm, ops::Range};
 use alloc::vec::Vec;
 use log::{debug, info};
 use riscv::register::satp;
-use xmas_elf::ElfFile;
 
 use crate::{
-    constant::{MEM_END_PPN, MMIO, TRAMPOLINE_VPN, TRAP_CONTEXT_VPN, USER_STACK_SIZE_BY_PAGE},
+    constant::{
+        MEM_END_PPN, MMAP_BASE, MMAP_TOP, MMIO, PAGE_SIZE, SIGRETURN_VA, TRAMPOLINE_VPN,
+        USER_STACK_SIZE_BY_PAGE, USER_STACK_TOP,
+    },
     mm::address::VirtAddr,
-    sync::up::UPSafeCell,
+    sbi::remote_sfence_vma_all,
+    sync::spin::SpinLock,
+    syscall::{Errno, SyscallResult},
 };
 
 use super::{
     address::{
         PageAlignedVirtBufIter, PhysPageNum, PhysPageSpan, Reader, VirtPageNum, VirtPageSpan,
     },
+    elf,
     page_table::{PTEFlags, PageTableEntry, TopLevelEntry},
     virt_mem_area::{MapType, Permission, VirtMemArea},
 };
@@ -25,14 +30,23 @@ pub struct MemSet {
     entry: TopLevelEntry,
     vmas: Vec<VirtMemArea>,
     heap_start: VirtPageNum,
+    //主线程用户栈的栈顶, 栈vma的结尾总是它
+    stack_top: VirtPageNum,
 }
 
 impl Clone for MemSet {
     fn clone(&self) -> Self {
         let mut mem_set = Self::new_bare();
         mem_set.map_trampoline();
+        mem_set.map_sigreturn();
         mem_set.heap_start = self.heap_start;
+        mem_set.stack_top = self.stack_top;
         for vma in &self.vmas {
+            //用户态可以访问的区域写时复制, 其余的(trap上下文)内核会直接按物理地址写, 只能立即复制
+            if vma.is_framed() && vma.perm().contains(Permission::U) {
+                mem_set.vmas.push(vma.clone_cow(self.entry, mem_set.entry));
+                continue;
+            }
             //克隆一个新vma包括range和perm等信息, 但是还没有建立vpn到ppn的映射关系,
             //因为新的内存空间会映射到不同的ppn上旧的ppn对于新内存空间是没有意义的所以映射关系要等下自己创建
             let new = vma.clone();
@@ -43,6 +57,8 @@ impl Clone for MemSet {
             //拷贝数据
             iter_dst.read(iter_src);
         }
+        //自己的页表项去掉了写权限, 所有hart上的快表都要刷新
+        remote_sfence_vma_all();
         mem_set
     }
 }
@@ -53,6 +69,7 @@ impl MemSet {
             entry: TopLevelEntry::new(),
             vmas: Vec::new(),
             heap_start: VirtPageNum::NULL,
+            stack_top: VirtPageNum::NULL,
         }
     }
 
@@ -66,10 +83,17 @@ impl MemSet {
         self.vmas.push(vma);
     }
 
-    pub fn push_vma_with_data(&mut self, mut vma: VirtMemArea, src: &[u8]) {
-        vma.map(self.entry);
-        vma.memcpy(self.entry, src);
-        self.vmas.push(vma);
+    //向已经映射的地址写入数据, 不检查权限, 只在加载程序时使用
+    fn write_bytes(&self, va: VirtAddr, mut src: &[u8]) {
+        let mut va = va.raw();
+        while !src.is_empty() {
+            let (vpn, offset) = VirtAddr(va).split();
+            let len = src.len().min(PAGE_SIZE - offset);
+            let ppn = self.translate(vpn).unwrap().ppn();
+            ppn.read_as_bytes_array()[offset..offset + len].copy_from_slice(&src[..len]);
+            src = &src[len..];
+            va += len;
+        }
     }
 
     //调用者要保证和已存在的vma不冲突
@@ -77,6 +101,116 @@ impl MemSet {
         self.push_vma(VirtMemArea::new(range, MapType::Framed, perm))
     }
 
+    //第一次访问时才分配物理页
+    pub fn insert_lazy_area(&mut self, range: VirtPageSpan, perm: Permission) {
+        self.push_vma(VirtMemArea::new(range, MapType::Lazy, perm))
+    }
+
+    //删除以start开头的vma并解除映射, 不存在时什么也不做
+    pub fn remove_area(&mut self, start: VirtPageNum) {
+        if let Some(idx) = self.vmas.iter().position(|vma| vma.start() == start) {
+            let mut vma = self.vmas.remove(idx);
+            vma.unmap(self.entry);
+        }
+    }
+
+    //在mmap区域中找一段长为len页的空闲区域
+    pub fn find_free_area(&self, len: usize) -> Option<VirtPageNum> {
+        let top = VirtAddr(MMAP_TOP).floor();
+        let mut start = VirtAddr(MMAP_BASE).floor();
+        loop {
+            let end = start + len;
+            if end > top {
+                return None;
+            }
+            match self
+                .vmas
+                .iter()
+                .filter(|vma| vma.start() < end && start < vma.end())
+                .map(|vma| vma.end())
+                .max()
+            {
+                Some(next) => start = next,
+                None => return Some(start),
+            }
+        }
+    }
+
+    //range中原有的映射会先被解除
+    pub fn mmap(&mut self, range: VirtPageSpan, map_type: MapType, perm: Permission) {
+        self.munmap(range);
+        self.push_vma(VirtMemArea::new(range, map_type, perm));
+    }
+
+    //把部分落在range中的用户态vma切开, 返回落在range中的vma的下标(升序)
+    fn split_range(&mut self, range: VirtPageSpan) -> Vec<usize> {
+        let mut inside = Vec::new();
+        for idx in 0..self.vmas.len() {
+            let vma = &mut self.vmas[idx];
+            if !vma.perm().contains(Permission::U)
+                || vma.end() <= range.start
+                || range.end <= vma.start()
+            {
+                continue;
+            }
+            if range.end < vma.end() {
+                let tail = vma.split_off(range.end);
+                self.vmas.push(tail);
+            }
+            let vma = &mut self.vmas[idx];
+            if vma.start() < range.start {
+                let middle = vma.split_off(range.start);
+                self.vmas.push(middle);
+                inside.push(self.vmas.len() - 1);
+            } else {
+                inside.push(idx);
+            }
+        }
+        inside.sort_unstable();
+        inside
+    }
+
+    //解除range中的用户态映射, 部分落在range中的vma会被切开
+    pub fn munmap(&mut self, range: VirtPageSpan) {
+        for idx in self.split_range(range).into_iter().rev() {
+            self.vmas.remove(idx).unmap(self.entry);
+        }
+    }
+
+    //range中有页不属于任何用户态vma时返回ENOMEM, 给不可写的共享文件映射加上写权限时返回EACCES
+    //出错时什么也不修改
+    pub fn mprotect(&mut self, range: VirtPageSpan, perm: Permission) -> SyscallResult<()> {
+        let mut vmas: Vec<_> = self
+            .vmas
+            .iter()
+            .filter(|vma| {
+                vma.perm().contains(Permission::U)
+                    && vma.start() < range.end
+                    && range.start < vma.end()
+            })
+            .collect();
+        if perm.contains(Permission::W) && vmas.iter().any(|vma| !vma.can_write()) {
+            return Err(Errno::EACCES);
+        }
+        vmas.sort_unstable_by_key(|vma| vma.start());
+        let mut covered = range.start;
+        for vma in vmas {
+            if vma.start() > covered {
+                return Err(Errno::ENOMEM);
+            }
+            covered = covered.max(vma.end());
+        }
+        if covered < range.end {
+            return Err(Errno::ENOMEM);
+        }
+        for idx in self.split_range(range) {
+            self.vmas[idx].set_perm(self.entry, perm);
+        }
+        //其他hart的快表中可能还有旧的权限
+        remote_sfence_vma_all();
+        Ok(())
+    }
+
     fn insert_identical_area(&mut self, range: PhysPageSpan, perm: Permission) {
         self.push_vma(VirtMemArea::new(
             range.identical(),
@@ -93,6 +227,15 @@ impl MemSet {
         )
     }
 
+    //用户态可以执行的sigreturn跳板, 和内核共用同一个物理页
+    fn map_sigreturn(&mut self) {
+        self.entry.map(
+            VirtAddr(SIGRETURN_VA).floor(),
+            super::kernel_layout::ssigreturn(),
+            PTEFlags::READ | PTEFlags::EXEC | PTEFlags::USER,
+        )
+    }
+
     pub fn new_kernel() -> Self {
         use super::kernel_layout::*;
         let mut mem_set = Self::new_bare();
@@ -141,66 +284,54 @@ impl MemSet {
     }
 
     //内存描述符, 用户栈底, 程序入口地址
-    pub fn from_elf(elf_data: &[u8]) -> (Self, VirtPageNum, VirtAddr) {
+    //默认拒绝同时可写可执行的段, 开启allow_wx feature后放行
+    pub fn from_elf(elf_data: &[u8]) -> SyscallResult<(Self, VirtPageNum, VirtAddr)> {
+        //先检查完整个文件再分配内存, 失败时旧的地址空间不受影响
+        let image = elf::parse(elf_data)?;
+        if !cfg!(feature = "allow_wx")
+            && image
+                .segments
+                .iter()
+                .any(|seg| seg.perm.contains(Permission::W | Permission::X))
+        {
+            return Err(Errno::EACCES);
+        }
         let mut mem_set = Self::new_bare();
         //最高地址映射到跳板代码
         mem_set.map_trampoline();
-        let elf = ElfFile::new(elf_data).unwrap();
-        let header = elf.header;
-        assert_eq!(header.pt1.magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf");
-        let ph_count = header.pt2.ph_count();
+        mem_set.map_sigreturn();
         let mut max_end_vpn = VirtPageNum::NULL;
-        for i in 0..ph_count {
-            let ph = elf.program_header(i).unwrap();
-            if let xmas_elf::program::Type::Load = ph.get_type().unwrap() {
-                let start_va: VirtAddr = ph.virtual_addr().into();
-                let end_va: VirtAddr = (ph.virtual_addr() + ph.mem_size()).into();
-                let mut perm = Permission::U;
-                let flags = ph.flags();
-                if flags.is_read() {
-                    perm |= Permission::R;
-                }
-                if flags.is_write() {
-                    perm |= Permission::W;
-                }
-                if flags.is_execute() {
-                    perm |= Permission::X;
-                }
-                let vma = VirtMemArea::new(
-                    (start_va.floor()..end_va.ceil()).into(),
-                    MapType::Framed,
-                    perm,
-                );
-                max_end_vpn = vma.end();
-                mem_set.push_vma_with_data(
-                    vma,
-                    &elf.input[ph.offset() as usize..][..ph.file_size() as usize],
-                );
-            }
+        for seg in &image.segments {
+            let vma = VirtMemArea::new(
+                (VirtAddr(seg.start).floor()..VirtAddr(seg.end).ceil()).into(),
+                MapType::Framed,
+                seg.perm,
+            );
+            max_end_vpn = vma.end();
+            mem_set.push_vma(vma);
+            //新分配的物理页已经清零, 超出文件内容的.bss部分不用再处理
+            mem_set.write_bytes(VirtAddr(seg.start), seg.data);
         }
-        let user_stack_top = max_end_vpn + 1usize; //空出一个页, 越界时就能触发页异常
-        let user_stack_bottom = user_stack_top + USER_STACK_SIZE_BY_PAGE;
-        //用户栈
-        mem_set.insert_framed_area(
-            (user_stack_top..user_stack_bottom).into(),
+        for &(addr, val) in &image.relocations {
+            mem_set.write_bytes(VirtAddr(addr), &val.to_le_bytes());
+        }
+        //用户栈, 缺页时向下增长
+        let stack_top = VirtAddr(USER_STACK_TOP).floor();
+        mem_set.push_vma(VirtMemArea::new(
+            (stack_top - USER_STACK_SIZE_BY_PAGE..stack_top).into(),
+            MapType::Stack,
             Permission::R | Permission::W | Permission::U,
-        );
-        //堆空间
-        mem_set.insert_framed_area(
-            (user_stack_bottom..user_stack_bottom).into(),
+        ));
+        mem_set.stack_top = stack_top;
+        //堆空间, 空出一个页, 越界时就能触发页异常
+        let heap_start = max_end_vpn + 1usize;
+        mem_set.insert_lazy_area(
+            (heap_start..heap_start).into(),
             Permission::R | Permission::W | Permission::U,
         );
-        mem_set.heap_start = user_stack_bottom;
-        //保存中断上下文的内存区域
-        mem_set.insert_framed_area(
-            (TRAP_CONTEXT_VPN..TRAMPOLINE_VPN).into(),
-            Permission::R | Permission::W,
-        );
-        (
-            mem_set,
-            user_stack_bottom,
-            (elf.header.pt2.entry_point() as usize).into(),
-        )
+        mem_set.heap_start = heap_start;
+        //保存中断上下文的内存区域由各个线程自己分配
+        Ok((mem_set, stack_top, VirtAddr(image.entry)))
     }
 
     pub fn token(&self) -> usize {
@@ -222,30 +353,119 @@ impl MemSet {
         self.entry.drop();
     }
 
-    pub fn heap_grow(&mut self, new_end: VirtPageNum) {
-        self.vmas
+    pub fn heap_start(&self) -> VirtPageNum {
+        self.heap_start
+    }
+
+    //栈最多能向下增长到这一页
+    fn stack_limit_vpn(&self, stack_limit: usize) -> VirtPageNum {
+        VirtPageNum(self.stack_top.0.saturating_sub(stack_limit / PAGE_SIZE))
+    }
+
+    //访问栈底之下的页时向下扩展栈, 扩展后的栈底之下仍要留一个不属于任何vma的保护页
+    fn grow_stack(&mut self, vpn: VirtPageNum, stack_limit: usize) -> bool {
+        if vpn < self.stack_limit_vpn(stack_limit) || vpn.0 == 0 {
+            return false;
+        }
+        //栈可能被mprotect切成了几段, vpn之上最近的vma是栈时从它向下扩展
+        let idx = match self
+            .vmas
+            .iter()
+            .enumerate()
+            .filter(|(_, vma)| vma.start() > vpn && vma.perm().contains(Permission::U))
+            .min_by_key(|(_, vma)| vma.start())
+        {
+            Some((idx, vma)) if vma.is_stack() => idx,
+            _ => return false,
+        };
+        let bottom = self.vmas[idx].start();
+        let guard = vpn - 1usize;
+        if self
+            .vmas
+            .iter()
+            .any(|vma| vma.start() < bottom && guard < vma.end())
+        {
+            return false;
+        }
+        self.vmas[idx].prepend_to(self.entry, vpn);
+        true
+    }
+
+    //访问落在栈的保护页或者rlimit之外, 用来报告栈溢出
+    pub fn is_stack_overflow(&self, va: VirtAddr, stack_limit: usize) -> bool {
+        let vpn = va.floor();
+        let lowest = self.stack_limit_vpn(stack_limit);
+        vpn < self.stack_top
+            && vpn + 1usize >= lowest
+            && !self.vmas.iter().any(|vma| vma.contains(vpn))
+    }
+
+    //处理用户态的页异常, 返回false时说明是非法访问
+    //stack_limit是栈的rlimit, 单位是字节
+    pub fn handle_page_fault(&mut self, va: VirtAddr, write: bool, stack_limit: usize) -> bool {
+        let vpn = va.floor();
+        if !self.vmas.iter().any(|vma| vma.contains(vpn)) && !self.grow_stack(vpn, stack_limit) {
+            return false;
+        }
+        let entry = self.entry;
+        let vma = match self
+            .vmas
             .iter_mut()
-            .find(|vma| vma.start() == self.heap_start)
-            .unwrap()
-            .append_to(self.entry, new_end)
+            .find(|vma| vma.contains(vpn) && vma.perm().contains(Permission::U))
+        {
+            Some(vma) => vma,
+            None => return false,
+        };
+        //mprotect去掉写权限后写时复制的页仍然带着COW标记, 要先检查vma的权限
+        if write && !vma.perm().contains(Permission::W) {
+            return false;
+        }
+        if write && entry.resolve_cow(vpn) {
+            return true;
+        }
+        vma.fault_in(entry, vpn, write)
     }
 
+    //堆可能被mprotect切成了几段, 它们都在heap_start和mmap区域之间
+    fn is_heap(&self, vma: &VirtMemArea) -> bool {
+        vma.perm().contains(Permission::U)
+            && vma.start() >= self.heap_start
+            && vma.start() < VirtAddr(MMAP_BASE).floor()
+    }
+
+    //最后一段的结尾就是堆顶
+    pub fn heap_grow(&mut self, new_end: VirtPageNum) {
+        let idx = (0..self.vmas.len())
+            .filter(|&idx| self.is_heap(&self.vmas[idx]))
+            .max_by_key(|&idx| self.vmas[idx].start())
+            .unwrap();
+        self.vmas[idx].append_to(self.entry, new_end)
+    }
+
+    //整段落在新堆顶之上的部分直接删除, 但第一段总是保留
     pub fn heap_shrink(&mut self, new_end: VirtPageNum) {
-        self.vmas
-            .iter_mut()
-            .find(|vma| vma.start() == self.heap_start)
-            .unwrap()
-            .shrink_to(self.entry, new_end)
+        let mut idx = 0;
+        while idx < self.vmas.len() {
+            let vma = &self.vmas[idx];
+            if !self.is_heap(vma) || vma.end() <= new_end {
+                idx += 1;
+            } else if vma.start() >= new_end && vma.start() != self.heap_start {
+                self.vmas.remove(idx).unmap(self.entry);
+            } else {
+                self.vmas[idx].shrink_to(self.entry, new_end);
+                idx += 1;
+            }
+        }
     }
 }
 
 lazy_static! {
-    pub static ref KERNEL_MEM_SPACE: UPSafeCell<MemSet> = unsafe {
+    pub static ref KERNEL_MEM_SPACE: SpinLock<MemSet> = {
         info!("[kernel] init kernel memory space");
-        UPSafeCell::new(MemSet::new_kernel())
+        SpinLock::new(MemSet::new_kernel())
     };
 }
 
 pub fn kernel_token() -> usize {
-    KERNEL_MEM_SPACE.exclusive_access().token()
+    KERNEL_MEM_SPACE.lock().token()
 }
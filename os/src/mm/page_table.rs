@@ -7,7 +7,8 @@ use super::{
     frame_alloc::ALLOCATOR,
     virt_mem_area::Permission as VMAPermission,
 };
-use alloc::{string::String, vec::Vec};
+use crate::process::processor::fault_in_current;
+use crate::sbi::remote_sfence_vma_all;
 use bitflags::*;
 use log::{debug, info};
 
@@ -33,6 +34,9 @@ impl From<VMAPermission> for PTEFlags {
 #[derive(Clone, Copy)]
 pub struct PageTableEntry(pub usize);
 
+//写时复制的页去掉了写权限, 用留给软件的RSW中的一位标记
+const PTE_COW: usize = 1 << 8;
+
 //64 reserved 54 pyhs_pager_num 10 rsw 8 DAGUEWRV 0
 impl PageTableEntry {
     pub fn new(ppn: PhysPageNum, flags: PTEFlags) -> Self {
@@ -62,6 +66,22 @@ impl PageTableEntry {
     pub fn is_valid(self) -> bool {
         self.flags().contains(PTEFlags::VAILD)
     }
+
+    pub fn is_cow(self) -> bool {
+        self.0 & PTE_COW != 0
+    }
+
+    pub fn is_readable(self) -> bool {
+        self.flags().contains(PTEFlags::READ)
+    }
+
+    pub fn is_writable(self) -> bool {
+        self.flags().contains(PTEFlags::WRITE)
+    }
+
+    pub fn is_user(self) -> bool {
+        self.flags().contains(PTEFlags::USER)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -82,9 +102,9 @@ impl TopLevelEntry {
                 .iter()
                 .filter(|entry| entry.is_valid())
                 .map(|entry| entry.ppn())
-                .for_each(|ppn| ALLOCATOR.exclusive_access().dealloc(ppn))
+                .for_each(|ppn| ALLOCATOR.lock().dealloc(ppn))
         } else {
-            ALLOCATOR.exclusive_access().dealloc(ppn)
+            ALLOCATOR.lock().dealloc(ppn)
         }
     }
 
@@ -94,7 +114,7 @@ impl TopLevelEntry {
     }
 
     pub fn new() -> Self {
-        let frame = ALLOCATOR.exclusive_access().alloc();
+        let frame = ALLOCATOR.lock().alloc();
         Self(frame)
     }
 
@@ -106,9 +126,51 @@ impl TopLevelEntry {
         Self(PhysPageNum(stap & PPN_MASK))
     }
 
+    //V为1且RWX都为0的页表项会被硬件当作指向下一级页表, 不可访问的页只能去掉V位
+    //去掉V位的页表项仍然保留物理页号, 访问时触发页异常
+    fn leaf_flags(flags: PTEFlags) -> PTEFlags {
+        if flags.intersects(PTEFlags::READ | PTEFlags::WRITE | PTEFlags::EXEC) {
+            PTEFlags::VAILD | flags
+        } else {
+            flags
+        }
+    }
+
     pub fn map(self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
         let pte = self.find_pte_or_create(vpn);
-        *pte = PageTableEntry::new(ppn, PTEFlags::VAILD | flags);
+        *pte = PageTableEntry::new(ppn, Self::leaf_flags(flags));
+    }
+
+    //以写时复制的方式映射, 写的时候才复制出独占的物理页
+    pub fn map_cow(self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_or_create(vpn);
+        let flags = Self::leaf_flags(flags) - PTEFlags::WRITE;
+        *pte = PageTableEntry(PageTableEntry::new(ppn, flags).0 | PTE_COW);
+    }
+
+    //vpn是写时复制的页时, 换成独占的物理页并恢复写权限, 返回是否做了处理
+    //只有一个引用时不必复制, 直接恢复写权限
+    pub fn resolve_cow(self, vpn: VirtPageNum) -> bool {
+        let pte = match self.find_pte(vpn) {
+            Some(pte) if pte.is_valid() && pte.is_cow() => pte,
+            _ => return false,
+        };
+        let (ppn, flags) = pte.split();
+        let mut allocator = ALLOCATOR.lock();
+        let new = if allocator.ref_count(ppn) == 1 {
+            ppn
+        } else {
+            let new = allocator.alloc();
+            new.read_as_bytes_array()
+                .copy_from_slice(ppn.read_as_bytes_array());
+            allocator.dealloc(ppn);
+            new
+        };
+        *pte = PageTableEntry::new(new, flags | PTEFlags::WRITE);
+        drop(allocator);
+        //同一进程的其他线程可能在别的hart上, 它们的快表里还是旧的映射
+        remote_sfence_vma_all();
+        true
     }
 
     pub fn unmap(self, vpn: VirtPageNum) {
@@ -146,7 +208,7 @@ impl TopLevelEntry {
                 return pte;
             }
             if !pte.is_valid() {
-                let frame = ALLOCATOR.exclusive_access().alloc();
+                let frame = ALLOCATOR.lock().alloc();
                 *pte = PageTableEntry::new_valid(frame);
             }
             ppn = pte.ppn();
@@ -155,38 +217,31 @@ impl TopLevelEntry {
     }
 
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.find_pte(vpn).map(|pte| *pte)
+        self.find_pte(vpn)
+            .filter(|pte| pte.is_valid())
+            .map(|pte| *pte)
     }
 
-    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
-        let (vpn, offset) = va.split();
-        self.translate(vpn)
-            .map(|entry| entry.ppn().phys_addr(offset))
+    //和translate不同, 映射了物理页但不可访问的页也会返回
+    pub fn lookup(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.find_pte(vpn).filter(|pte| pte.0 != 0).map(|pte| *pte)
     }
 
-    pub fn translate_virt_str(self, virt_str: *const u8) -> String {
-        unsafe {
-            let mut s = Vec::new();
-            let mut va = VirtAddr(virt_str as usize);
-            loop {
-                let c = *(self.translate_va(va).unwrap().0 as *const u8);
-                if c == 0 {
-                    break;
-                } else {
-                    s.push(c);
-                    va.0 += 1;
-                }
-            }
-            String::from_utf8_unchecked(s)
+    //内核直接按物理地址访问用户内存, 不会触发页异常, 访问之前要自己处理写时复制和缺页
+    //和用户态访问一样要检查vma的权限, 不能通过内核写只读的页
+    pub fn prepare_user_access(self, vpn: VirtPageNum, write: bool) {
+        let ready = self
+            .translate(vpn)
+            .map_or(false, |pte| !write || pte.is_writable());
+        if !ready {
+            fault_in_current(self.token(), vpn, write);
         }
     }
 
-    //要求对象的内存布局不能跨页
-    pub fn translate_virt_ref<T>(self, ptr: *const T) -> &'static T {
-        self.translate_va(VirtAddr(ptr as usize)).unwrap().as_ref()
-    }
-
-    pub fn translate_virt_mut<T>(self, ptr: *mut T) -> &'static mut T {
-        self.translate_va(VirtAddr(ptr as usize)).unwrap().as_mut()
+    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
+        let (vpn, offset) = va.split();
+        self.prepare_user_access(vpn, false);
+        self.translate(vpn)
+            .map(|entry| entry.ppn().phys_addr(offset))
     }
 }
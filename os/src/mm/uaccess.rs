@@ -0,0 +1,124 @@
+//! 内核访问用户内存的接口
+//! 访问前检查页是否映射以及U/R/W权限, 地址不合法时返回EFAULT而不是panic
+
+use core::mem::{size_of, MaybeUninit};
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    constant::{PAGE_SIZE, VA_WIDTH},
+    syscall::{Errno, SyscallResult},
+};
+
+use super::{
+    address::{PhysPageNum, VirtAddr, VirtPageNum},
+    page_table::TopLevelEntry,
+};
+
+// 用户态只能使用低半部分的地址空间
+const USER_SPACE_END: usize = 1 << (VA_WIDTH - 1);
+
+// 检查用户态能否以给定方式访问vpn处的页, 需要时先处理缺页和写时复制
+pub fn user_page(
+    page_table: TopLevelEntry,
+    vpn: VirtPageNum,
+    write: bool,
+) -> SyscallResult<PhysPageNum> {
+    page_table.prepare_user_access(vpn, write);
+    match page_table.translate(vpn) {
+        Some(pte) if pte.is_user() && pte.is_readable() && (!write || pte.is_writable()) => {
+            Ok(pte.ppn())
+        }
+        _ => Err(Errno::EFAULT),
+    }
+}
+
+// 检查[addr, addr + len)都在用户地址空间中
+pub fn check_user_range(addr: usize, len: usize) -> SyscallResult<()> {
+    match addr.checked_add(len) {
+        Some(end) if end <= USER_SPACE_END => Ok(()),
+        _ => Err(Errno::EFAULT),
+    }
+}
+
+// 按页切开[addr, addr + len), 对每一段调用f(物理页中的切片, 在整个区间中的偏移)
+fn for_each_page(
+    page_table: TopLevelEntry,
+    addr: usize,
+    len: usize,
+    write: bool,
+    mut f: impl FnMut(&'static mut [u8], usize),
+) -> SyscallResult<()> {
+    check_user_range(addr, len)?;
+    let mut done = 0;
+    while done < len {
+        let (vpn, offset) = VirtAddr(addr + done).split();
+        let chunk = (PAGE_SIZE - offset).min(len - done);
+        let ppn = user_page(page_table, vpn, write)?;
+        f(&mut ppn.read_as_bytes_array()[offset..offset + chunk], done);
+        done += chunk;
+    }
+    Ok(())
+}
+
+pub fn copy_bytes_from_user(
+    page_table: TopLevelEntry,
+    src: usize,
+    dst: &mut [u8],
+) -> SyscallResult<()> {
+    for_each_page(page_table, src, dst.len(), false, |page, done| {
+        dst[done..done + page.len()].copy_from_slice(page)
+    })
+}
+
+pub fn copy_bytes_to_user(page_table: TopLevelEntry, dst: usize, src: &[u8]) -> SyscallResult<()> {
+    for_each_page(page_table, dst, src.len(), true, |page, done| {
+        page.copy_from_slice(&src[done..done + page.len()])
+    })
+}
+
+// T只能是任意位模式都合法的普通数据
+pub fn copy_from_user<T: Copy>(page_table: TopLevelEntry, src: *const T) -> SyscallResult<T> {
+    let mut val = MaybeUninit::<T>::uninit();
+    let bytes =
+        unsafe { core::slice::from_raw_parts_mut(val.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    copy_bytes_from_user(page_table, src as usize, bytes)?;
+    Ok(unsafe { val.assume_init() })
+}
+
+pub fn copy_to_user<T: Copy>(page_table: TopLevelEntry, dst: *mut T, val: T) -> SyscallResult<()> {
+    let bytes =
+        unsafe { core::slice::from_raw_parts(&val as *const T as *const u8, size_of::<T>()) };
+    copy_bytes_to_user(page_table, dst as usize, bytes)
+}
+
+// 读取以0结尾的字符串, 包括结尾的0超过max_len字节时返回ENAMETOOLONG, 不是合法的utf8时返回EINVAL
+pub fn copy_str_from_user(
+    page_table: TopLevelEntry,
+    src: *const u8,
+    max_len: usize,
+) -> SyscallResult<String> {
+    let mut bytes = Vec::new();
+    let mut addr = src as usize;
+    loop {
+        if bytes.len() >= max_len {
+            return Err(Errno::ENAMETOOLONG);
+        }
+        check_user_range(addr, 1)?;
+        let (vpn, offset) = VirtAddr(addr).split();
+        let page = &user_page(page_table, vpn, false)?.read_as_bytes_array()[offset..];
+        //超出max_len的部分不再读入, 过长的字符串不会占用过多的内核堆
+        let page = &page[..page.len().min(max_len - bytes.len())];
+        match page.iter().position(|&c| c == 0) {
+            Some(end) => {
+                bytes.extend_from_slice(&page[..end]);
+                break;
+            }
+            None => {
+                bytes.extend_from_slice(page);
+                addr += page.len();
+            }
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| Errno::EINVAL)
+}
@@ -1,41 +1,77 @@
 #![allow(unused)]
 use core::ops::Range;
 
-use crate::mm::address::{PageAlignedVirtBufIter, Reader, UserBuffer};
-use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use yfs::vfs::Vnode;
+
+use crate::{
+    constant::PAGE_SIZE,
+    mm::address::{PageAlignedVirtBufIter, Reader, UserBuffer},
+};
 
 use super::{
     address::{PhysPageNum, VirtPageNum, VirtPageSpan},
     frame_alloc::ALLOCATOR,
-    page_table::TopLevelEntry,
+    page_table::{PTEFlags, TopLevelEntry},
 };
 
+//framed区域的vpn到ppn的映射关系以页表为准, 写时复制会在页表中替换物理页
+//lazy区域也是framed的, 只是第一次访问某一页时才分配物理页
+//mmap出来的区域都是按需分配的
+#[derive(Clone)]
 enum Map {
     Identical,
-    Framed(BTreeMap<VirtPageNum, PhysPageNum>),
+    Framed,
+    Lazy,
+    //用户栈, 和Lazy一样按需分配, 被mprotect切开后各段仍然能认出来
+    Stack,
+    Shared,
+    File(FileMapping),
 }
 
-impl Clone for Map {
-    fn clone(&self) -> Self {
+impl Map {
+    fn is_framed(&self) -> bool {
         match self {
-            Map::Identical => Map::Identical,
-            Map::Framed(map) => Map::Framed(BTreeMap::new()),
+            Map::Identical => false,
+            _ => true,
         }
     }
-}
 
-impl Map {
-    fn is_framed(&self) -> bool {
+    fn is_lazy(&self) -> bool {
+        matches!(self, Map::Lazy | Map::Stack | Map::Shared | Map::File(_))
+    }
+
+    //fork后父子进程看到的是同一份物理页, 而不是写时复制
+    fn is_shared(&self) -> bool {
         match self {
-            Map::Identical => false,
-            Map::Framed(_) => true,
+            Map::Shared => true,
+            Map::File(file) => file.shared,
+            _ => false,
         }
     }
 }
 
+//文件映射, 区域的第一页对应文件中的offset处
+#[derive(Clone)]
+pub struct FileMapping {
+    pub inode: Arc<Vnode>,
+    pub offset: usize,
+    //共享映射的修改在解除映射时写回文件, 私有映射的修改只有自己能看到
+    pub shared: bool,
+    //文件是否以可写方式打开, 之后用mprotect给共享映射加上写权限时要检查
+    pub writable: bool,
+}
+
 pub enum MapType {
     Identical,
     Framed,
+    //按需分配物理页, 用于堆和私有匿名映射
+    Lazy,
+    //用户栈, 缺页时向下增长
+    Stack,
+    //共享匿名映射, fork后父子进程共享
+    Shared,
+    File(FileMapping),
 }
 
 bitflags! {
@@ -68,7 +104,11 @@ impl VirtMemArea {
     pub fn new(vpn_range: VirtPageSpan, map_type: MapType, perm: Permission) -> Self {
         let map = match map_type {
             MapType::Identical => Map::Identical,
-            MapType::Framed => Map::Framed(BTreeMap::new()),
+            MapType::Framed => Map::Framed,
+            MapType::Lazy => Map::Lazy,
+            MapType::Stack => Map::Stack,
+            MapType::Shared => Map::Shared,
+            MapType::File(file) => Map::File(file),
         };
         Self {
             vpn_range,
@@ -77,16 +117,18 @@ impl VirtMemArea {
         }
     }
 
-    //传入一个顶层页表基址和一个虚拟页号, 让帧分配器分配一个物理页帧, 分别在页表和vma中建立映射关系
-    pub fn map_one(&mut self, page_table_entry: TopLevelEntry, vpn: VirtPageNum) {
+    //传入一个顶层页表基址和一个虚拟页号, 让帧分配器分配一个物理页帧, 在页表中建立映射关系
+    pub fn map_one(&self, page_table_entry: TopLevelEntry, vpn: VirtPageNum) {
         let ppn = match self.map {
             Map::Identical => PhysPageNum(vpn.0),
-            Map::Framed(ref mut map) => {
-                let ppn = ALLOCATOR.exclusive_access().alloc();
-                map.insert(vpn, ppn);
-                ppn
-            }
+            _ => ALLOCATOR.lock().alloc(),
         };
+        //文件映射的页第一次访问时从文件中读入, 超出文件末尾的部分是0
+        //sys_mmap检查过映射范围, 偏移不会超出u32
+        if let Map::File(file) = &self.map {
+            let offset = file.offset + (vpn.0 - self.vpn_range.start.0) * PAGE_SIZE;
+            file.inode.read(offset as u32, ppn.read_as_bytes_array());
+        }
         let pte_flags = self.perm.into();
         page_table_entry.map(vpn, ppn, pte_flags);
     }
@@ -103,16 +145,118 @@ impl VirtMemArea {
         self.vpn_range
     }
 
-    //传入一个顶层页表基址和一个被映射的虚拟页号, 从页表和vma中删除映射关系,
+    pub fn perm(&self) -> Permission {
+        self.perm
+    }
+
+    pub fn is_framed(&self) -> bool {
+        self.map.is_framed()
+    }
+
+    pub fn is_stack(&self) -> bool {
+        matches!(self.map, Map::Stack)
+    }
+
+    //共享文件映射的文件不可写时, 不能加上写权限
+    pub fn can_write(&self) -> bool {
+        match &self.map {
+            Map::File(file) => !file.shared || file.writable,
+            _ => true,
+        }
+    }
+
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.start <= vpn && vpn < self.vpn_range.end
+    }
+
+    //lazy区域中还没有被访问过的页没有映射
+    fn is_mapped(&self, page_table_entry: TopLevelEntry, vpn: VirtPageNum) -> bool {
+        if self.map.is_lazy() {
+            page_table_entry.lookup(vpn).is_some()
+        } else {
+            true
+        }
+    }
+
+    //第一次访问lazy区域中的页时分配物理页, 访问不合法时返回false
+    pub fn fault_in(
+        &mut self,
+        page_table_entry: TopLevelEntry,
+        vpn: VirtPageNum,
+        write: bool,
+    ) -> bool {
+        let allowed = if write {
+            self.perm.contains(Permission::W)
+        } else {
+            self.perm.intersects(Permission::R | Permission::X)
+        };
+        if !self.map.is_lazy() || !allowed || self.is_mapped(page_table_entry, vpn) {
+            return false;
+        }
+        self.map_one(page_table_entry, vpn);
+        true
+    }
+
+    //传入一个顶层页表基址和一个被映射的虚拟页号, 从页表中删除映射关系,
     pub fn unmap_one(&mut self, page_table_entry: TopLevelEntry, vpn: VirtPageNum) {
-        if let Map::Framed(ref mut map) = self.map {
-            let ppn = map.remove(&vpn).unwrap();
-            ALLOCATOR.exclusive_access().dealloc(ppn);
+        if !self.is_mapped(page_table_entry, vpn) {
+            return;
+        }
+        if self.map.is_framed() {
+            let ppn = page_table_entry.lookup(vpn).unwrap().ppn();
+            if let Map::File(file) = &self.map {
+                if file.shared {
+                    Self::write_back(file, self.vpn_range.start, vpn, ppn);
+                }
+            }
+            ALLOCATOR.lock().dealloc(ppn);
         }
         page_table_entry.unmap(vpn);
     }
 
+    //把共享文件映射中的一页写回文件, 不会让文件变长
+    fn write_back(file: &FileMapping, start: VirtPageNum, vpn: VirtPageNum, ppn: PhysPageNum) {
+        let offset = file.offset + (vpn.0 - start.0) * PAGE_SIZE;
+        let size = file.inode.size() as usize;
+        if offset >= size {
+            return;
+        }
+        let len = PAGE_SIZE.min(size - offset);
+        file.inode
+            .write(offset as u32, &ppn.read_as_bytes_array()[..len]);
+    }
+
+    //fork时使用, 新vma和自己共享所有的物理页, 私有区域可写的页在两边都变成写时复制
+    pub fn clone_cow(&self, src: TopLevelEntry, dst: TopLevelEntry) -> Self {
+        assert!(self.map.is_framed(), "vma must be framed");
+        let flags: PTEFlags = self.perm.into();
+        let shared = self.map.is_shared();
+        for vpn in self.vpn_range {
+            //共享区域还没访问过的页如果各自分配就不再共享了, 所以在fork前先全部分配, 不可读的页也一样
+            if shared && !self.is_mapped(src, vpn) {
+                self.map_one(src, vpn);
+            }
+            if !self.is_mapped(src, vpn) {
+                continue;
+            }
+            let ppn = src.lookup(vpn).unwrap().ppn();
+            ALLOCATOR.lock().share(ppn);
+            if shared {
+                dst.map(vpn, ppn, flags);
+            } else if self.perm.contains(Permission::W) {
+                src.map_cow(vpn, ppn, flags);
+                dst.map_cow(vpn, ppn, flags);
+            } else {
+                dst.map(vpn, ppn, flags);
+            }
+        }
+        self.clone()
+    }
+
     pub fn map(&mut self, page_table_entry: TopLevelEntry) {
+        if self.map.is_lazy() {
+            return;
+        }
         for vpn in self.vpn_range {
             self.map_one(page_table_entry, vpn)
         }
@@ -140,12 +284,67 @@ impl VirtMemArea {
             new_end >= self.vpn_range.end,
             "new_end must be greater than the end of vma"
         );
-        for vpn in VirtPageSpan::new(self.vpn_range.end..new_end) {
-            self.map_one(page_table_entry, vpn)
+        if !self.map.is_lazy() {
+            for vpn in VirtPageSpan::new(self.vpn_range.end..new_end) {
+                self.map_one(page_table_entry, vpn)
+            }
         }
         self.vpn_range.end = new_end;
     }
 
+    //修改权限, 已经映射的页同时修改页表项, 不可访问的页保留物理页
+    //私有区域中和别的进程共享的页仍然要写时复制
+    pub fn set_perm(&mut self, page_table_entry: TopLevelEntry, perm: Permission) {
+        self.perm = perm;
+        let flags: PTEFlags = perm.into();
+        let shared = self.map.is_shared();
+        for vpn in self.vpn_range {
+            if !self.is_mapped(page_table_entry, vpn) {
+                continue;
+            }
+            let pte = page_table_entry.lookup(vpn).unwrap();
+            let ppn = pte.ppn();
+            if !shared && (pte.is_cow() || ALLOCATOR.lock().ref_count(ppn) > 1) {
+                page_table_entry.map_cow(vpn, ppn, flags);
+            } else {
+                page_table_entry.map(vpn, ppn, flags);
+            }
+        }
+    }
+
+    //在at处把vma切成两段, 自己保留前一段, 返回后一段
+    pub fn split_off(&mut self, at: VirtPageNum) -> Self {
+        assert!(
+            at > self.vpn_range.start && at < self.vpn_range.end,
+            "at must be in the range of vma"
+        );
+        let mut map = self.map.clone();
+        if let Map::File(file) = &mut map {
+            file.offset += (at.0 - self.vpn_range.start.0) * PAGE_SIZE;
+        }
+        let tail = Self {
+            vpn_range: VirtPageSpan::new(at..self.vpn_range.end),
+            map,
+            perm: self.perm,
+        };
+        self.vpn_range.end = at;
+        tail
+    }
+
+    //向下扩展到new_start, 用于栈的增长
+    pub fn prepend_to(&mut self, page_table_entry: TopLevelEntry, new_start: VirtPageNum) {
+        assert!(
+            new_start <= self.vpn_range.start,
+            "new_start must be less than the start of vma"
+        );
+        if !self.map.is_lazy() {
+            for vpn in VirtPageSpan::new(new_start..self.vpn_range.start) {
+                self.map_one(page_table_entry, vpn)
+            }
+        }
+        self.vpn_range.start = new_start;
+    }
+
     pub fn memcpy(&mut self, page_table_entry: TopLevelEntry, src: &[u8]) {
         assert!(self.map.is_framed(), "vma must be framed");
         let len = src.len();
@@ -1,15 +1,24 @@
-use crate::{
-    fs::inode::{OSInode, OpenFlags},
-    sync::up::UPSafeCell,
-};
+use crate::fs::inode::{OSInode, OpenFlags};
 
 use super::pcb::ProcessControlBlock;
 
+// initproc永远不会退出, 它的进程控制块由各个线程通过裸指针共享
+pub struct InitProc(*mut ProcessControlBlock);
+
+unsafe impl Send for InitProc {}
+unsafe impl Sync for InitProc {}
+
+impl InitProc {
+    pub fn get(&self) -> &'static mut ProcessControlBlock {
+        unsafe { &mut *self.0 }
+    }
+}
+
 lazy_static! {
-    pub static ref INITPROC: UPSafeCell<ProcessControlBlock> = unsafe {
+    pub static ref INITPROC: InitProc = {
         let data = OSInode::open("initproc", OpenFlags::READ)
             .unwrap()
             .read_all();
-        UPSafeCell::new(ProcessControlBlock::initproc(&data))
+        InitProc(ProcessControlBlock::initproc(&data))
     };
 }
@@ -0,0 +1,131 @@
+use crate::{
+    syscall::{Errno, SyscallResult},
+    timer::{add_alarm, remove_alarm, TimeVal},
+};
+
+use super::{
+    pid::{task_find, Pid},
+    signal::SignalFlags,
+};
+
+// setitimer的which
+pub const ITIMER_REAL: usize = 0;
+pub const ITIMER_VIRTUAL: usize = 1;
+pub const ITIMER_PROF: usize = 2;
+
+// 和linux的struct itimerval布局相同
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct ITimerVal {
+    pub interval: TimeVal,
+    pub value: TimeVal,
+}
+
+impl ITimerVal {
+    pub fn is_valid(&self) -> bool {
+        self.interval.is_valid() && self.value.is_valid()
+    }
+}
+
+// 以时钟周期计, value为0表示没有启动
+// ITIMER_REAL的value是到期的时刻, 另外两个是剩余的CPU时间
+#[derive(Clone, Copy, Default)]
+struct ITimer {
+    value: usize,
+    interval: usize,
+}
+
+impl ITimer {
+    // 消耗了elapsed个周期的CPU时间, 到期时返回true并按interval重新计时
+    fn consume(&mut self, elapsed: usize) -> bool {
+        if self.value == 0 {
+            return false;
+        }
+        if self.value > elapsed {
+            self.value -= elapsed;
+            return false;
+        }
+        self.value = self.interval;
+        true
+    }
+}
+
+// 进程的三个间隔定时器, fork出的子进程不继承, exec时保留
+#[derive(Clone, Copy, Default)]
+pub struct ITimers([ITimer; 3]);
+
+impl ITimers {
+    pub fn get(&self, which: usize, now: usize) -> SyscallResult<ITimerVal> {
+        let timer = self.0.get(which).ok_or(Errno::EINVAL)?;
+        let value = match which {
+            //已经到期但还没有处理时报告最短的剩余时间
+            ITIMER_REAL if timer.value != 0 => timer.value.saturating_sub(now).max(1),
+            _ => timer.value,
+        };
+        Ok(ITimerVal {
+            interval: TimeVal::from_ticks(timer.interval),
+            value: TimeVal::from_ticks(value),
+        })
+    }
+
+    // 调用者要先用get检查which
+    pub fn set(&mut self, which: usize, new: ITimerVal, now: usize, pid: Pid) {
+        //不足一个周期的非零时间按一个周期算, 否则会被当成取消定时器
+        let ticks = |val: &TimeVal| match val.to_ticks() {
+            0 if val.sec != 0 || val.usec != 0 => 1,
+            ticks => ticks,
+        };
+        let mut value = ticks(&new.value);
+        if which == ITIMER_REAL {
+            remove_alarm(pid);
+            if value != 0 {
+                value = value.saturating_add(now);
+                add_alarm(value, pid);
+            }
+        }
+        self.0[which] = ITimer {
+            value,
+            interval: ticks(&new.interval),
+        };
+    }
+
+    // 进程消耗了elapsed个周期的CPU时间, 返回到期的定时器对应的信号
+    // ITIMER_VIRTUAL只计用户态时间, ITIMER_PROF还计入内核态时间
+    pub fn consume(&mut self, elapsed: usize, user: bool) -> SignalFlags {
+        let mut signals = SignalFlags::empty();
+        if user && self.0[ITIMER_VIRTUAL].consume(elapsed) {
+            signals |= SignalFlags::SIGVTALRM;
+        }
+        if self.0[ITIMER_PROF].consume(elapsed) {
+            signals |= SignalFlags::SIGPROF;
+        }
+        signals
+    }
+
+    // ITIMER_REAL在expire时刻到期, 周期定时器从到期的时刻开始重新计时
+    // 定时器被修改或者取消后, 之前登记的到期时刻就过时了, 返回false
+    fn expire(&mut self, expire: usize, pid: Pid) -> bool {
+        let timer = &mut self.0[ITIMER_REAL];
+        if timer.value != expire {
+            return false;
+        }
+        timer.value = match timer.interval {
+            0 => 0,
+            interval => expire.saturating_add(interval),
+        };
+        if timer.value != 0 {
+            add_alarm(timer.value, pid);
+        }
+        true
+    }
+}
+
+// 由check_timers调用, 进程可能已经退出了
+pub fn alarm(pid: Pid, expire: usize) {
+    if let Some(task) = task_find(pid) {
+        let task = unsafe { &mut *task };
+        if !task.is_zombie() && task.itimers.expire(expire, pid) {
+            task.raise(SignalFlags::SIGALRM);
+        }
+    }
+}
@@ -1,8 +1,13 @@
 pub mod context;
 pub mod initproc;
+pub mod itimer;
 pub mod pcb;
 pub mod pid;
 pub mod processor;
 pub mod queue;
-pub mod switch;
+pub mod rlimit;
+pub mod rusage;
 pub mod signal;
+pub mod switch;
+pub mod tcb;
+pub mod wait_queue;
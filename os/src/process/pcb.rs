@@ -1,19 +1,25 @@
-use core::iter::once;
-use core::mem::size_of;
+use core::{iter, mem::size_of};
 
 use crate::fs::stdio::{stderr, stdin, stdout};
 use crate::mm::page_table::TopLevelEntry;
 use crate::process::processor::PROCESSOR;
-use crate::types::CStr;
+use crate::sync::{
+    condvar::Condvar, deadlock::DeadlockDetector, mutex::Mutex, semaphore::Semaphore,
+};
+use crate::syscall::{Errno, SyscallResult};
 use crate::{
-    constant::{PAGE_MASK, TRAP_CONTEXT_VPN},
+    constant::{
+        ARG_MAX, ARG_MAX_LIMIT, DEFAULT_STACK_LIMIT, MMAP_BASE, PAGE_MASK, PAGE_SIZE, SIGRETURN_VA,
+    },
     fs::File,
     mm::{
-        address::{PhysPageNum, VirtAddr},
-        kernel_stack::KernelStack,
-        mem_set::{MemSet, KERNEL_MEM_SPACE},
+        address::{PhysPageNum, VirtAddr, VirtPageSpan},
+        mem_set::{kernel_token, MemSet},
+        uaccess::{copy_bytes_to_user, copy_to_user},
+        virt_mem_area::Permission,
     },
-    process::{context::Context as TaskContext, pid::Pid},
+    process::pid::Pid,
+    timer::get_time,
     trap::context::Context as TrapContext,
     trap::trap_handler,
 };
@@ -22,181 +28,455 @@ use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
 use log::error;
 
 use super::initproc::INITPROC;
-use super::pid::{self, task_delete};
-use super::signal::{SignalActions, SignalFlags};
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub enum State {
-    Ready,
-    Running,
-    Zombie,
-}
+use super::itimer::ITimers;
+use super::pid::{self, task_delete, Allocator};
+use super::queue::SchedInfo;
+use super::rlimit::RLimit;
+use super::rusage::Usage;
+use super::signal::{
+    SigActionFlags, SigInfo, Signal, SignalAction, SignalActions, SignalFlags, SignalFrame,
+    SIGCHLD, SIGSEGV, SIG_DFL, SIG_IGN,
+};
+use super::tcb::{trap_ctx_vpn, user_stack_span, ThreadControlBlock};
+use super::wait_queue::{wakeup, WaitQueue};
 
 type FdTable = Vec<Option<Arc<dyn File + Send + Sync>>>;
 
+//进程的退出状态, waitpid据此区分正常退出和被信号杀死
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Exited(i32),
+    Signaled(Signal),
+}
+
+impl ExitStatus {
+    //写回用户态的状态字, 和linux相同: 正常退出时低8位的退出码放在8~15位, 被杀死时低7位是信号
+    pub fn wait_status(self) -> i32 {
+        match self {
+            Self::Exited(code) => (code & 0xff) << 8,
+            Self::Signaled(signal) => signal & 0x7f,
+        }
+    }
+
+    //进程退出时其余线程的退出码
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Exited(code) => code,
+            Self::Signaled(signal) => -signal,
+        }
+    }
+}
+
 pub struct ProcessControlBlock {
     // 在整个生命周期中, pid不会改变
     pub pid: Pid,
-    // 内核栈的代理对象, 在整个生命周期中, 该对象代理的内核栈不会改变
-    pub kernel_stack: KernelStack,
-    //task上下文
-    pub task_context: TaskContext,
-    //进程状态
-    pub state: State,
+    //所有线程都退出后进程成为僵尸进程, 等待父进程回收
+    pub zombie: bool,
+    //某个线程调用了exit_group或者进程被信号杀死, 其余线程回到用户态前会自行退出
+    pub exiting: bool,
     //内存描述符
     pub mem_set: MemSet,
-    //trap上下文的物理页号
-    pub trap_ctx_ppn: PhysPageNum,
-    pub trap_ctx_backup: TrapContext,
+    //进程中的线程, 下标为tid
+    pub threads: Vec<Option<Box<ThreadControlBlock>>>,
+    pub tid_allocator: Allocator,
     //记录消耗了多少内存
     pub base_size: usize,
     //堆底
     pub heap_btm: usize,
     //堆顶
     pub brk: usize,
-    pub exit_code: i32,
+    pub exit_status: ExitStatus,
     pub children: Vec<*mut Self>,
-    //nullable
+    //nullable, 父进程不等待的僵尸进程也为空, 由调度器在它切换出去后释放
     pub parent: *mut Self,
+    //阻塞在waitpid上等待子进程退出
+    pub wait_queue: WaitQueue,
+    //阻塞在waittid上等待本进程中的线程退出
+    pub thread_wait_queue: WaitQueue,
     pub fd_table: FdTable,
     pub signals: SignalFlags,
     pub signal_mask: SignalFlags,
+    //sigsuspend临时替换掉的屏蔽字, 处理完信号后恢复
+    pub saved_mask: Option<SignalFlags>,
     pub signal_actions: SignalActions,
     pub frozen: bool,
-    pub handling_sig: Option<usize>,
+    //最近一次访存出错的地址, 随SIGSEGV一起报告
+    pub fault_addr: usize,
+    //主线程用户栈的大小限制, fork和exec时保留
+    pub stack_rlimit: RLimit,
+    //进程自己的资源使用情况, exec时保留
+    pub usage: Usage,
+    //已经被wait回收的子进程(包括它们回收的子进程)的资源使用情况
+    pub children_usage: Usage,
+    //setitimer设置的定时器, exec时保留
+    pub itimers: ITimers,
+    //线程同步原语, 下标就是用户态看到的id
+    pub mutex_list: Vec<Option<Box<dyn Mutex>>>,
+    pub semaphore_list: Vec<Option<Box<Semaphore>>>,
+    pub condvar_list: Vec<Option<Box<Condvar>>>,
+    pub deadlock_detector: DeadlockDetector,
 }
 
 impl Drop for ProcessControlBlock {
     fn drop(&mut self) {
         task_delete(self.pid);
-        pid::ALLOCATOR.exclusive_access().dealloc(self.pid);
+        pid::ALLOCATOR.lock().dealloc(self.pid.0);
     }
 }
 
 unsafe impl Send for ProcessControlBlock {}
 
-impl ProcessControlBlock {
-    pub fn task_ctx(&mut self) -> *mut TaskContext {
-        &mut self.task_context as *mut _
+// auxv的类型, 数值与linux相同
+const AT_NULL: usize = 0;
+const AT_PAGESZ: usize = 6;
+const AT_ENTRY: usize = 9;
+const AT_RANDOM: usize = 25;
+// auxv的项数, 包括结尾的AT_NULL
+const AUXV_LEN: usize = 4;
+
+//没有硬件随机数源, 用启动以来的时钟周期数经过splitmix64混合得到
+fn random_bytes() -> [u8; 16] {
+    let mut state = get_time() as u64;
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_exact_mut(8) {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        chunk.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
     }
+    bytes
+}
 
-    pub fn initproc(elf_data: &[u8]) -> Self {
-        let (mem_set, user_sp, entry) = MemSet::from_elf(elf_data);
+//参数和环境变量在初始栈上占用的空间, 包括字符串, 随机字节, 指针数组, auxv以及对齐的空隙
+pub fn args_size(argv: &[String], envp: &[String]) -> usize {
+    let strs: usize = argv.iter().chain(envp).map(|s| s.len() + 1).sum();
+    let words = 1 + argv.len() + 1 + envp.len() + 1 + 2 * AUXV_LEN;
+    strs + 16 + words * size_of::<usize>() + 15
+}
 
-        //得到中断上下文的物理页号
-        let trap_ctx_ppn = mem_set.translate(TRAP_CONTEXT_VPN).unwrap().ppn();
-        let pid = pid::ALLOCATOR.exclusive_access().alloc();
-        let kernel_stack = KernelStack::new(pid);
+//按照RISC-V SysV ABI在栈顶stack_top下面放置程序的初始参数, 从高地址到低地址依次是:
+//参数和环境变量字符串, AT_RANDOM指向的16个随机字节, auxv, envp, argv, argc
+//返回(sp, argv, envp), sp指向argc并且按16字节对齐
+fn push_args(
+    page_table: TopLevelEntry,
+    stack_top: usize,
+    entry: usize,
+    argv: &[String],
+    envp: &[String],
+) -> SyscallResult<(usize, usize, usize)> {
+    let mut top = stack_top;
+    let mut strs = Vec::with_capacity(argv.len() + envp.len());
+    for s in argv.iter().chain(envp) {
+        top -= s.len() + 1;
+        copy_bytes_to_user(page_table, top, s.as_bytes())?;
+        copy_to_user(page_table, (top + s.len()) as *mut u8, 0)?;
+        strs.push(top);
+    }
+    top -= 16;
+    let random = top;
+    copy_to_user(page_table, random as *mut [u8; 16], random_bytes())?;
 
-        let user_stack_btm = user_sp.floor().0;
-        let kernel_stack_btm = kernel_stack.btm(pid).0;
-        let trap_ctx = TrapContext::new(
-            entry.0,
-            user_stack_btm,
-            KERNEL_MEM_SPACE.exclusive_access().token(),
-            kernel_stack_btm,
-            trap_handler as usize,
-        );
+    let (argv_ptrs, envp_ptrs) = strs.split_at(argv.len());
+    let auxv: [(usize, usize); AUXV_LEN] = [
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_ENTRY, entry),
+        (AT_RANDOM, random),
+        (AT_NULL, 0),
+    ];
+    let words: Vec<usize> = iter::once(argv.len())
+        .chain(argv_ptrs.iter().copied())
+        .chain(iter::once(0))
+        .chain(envp_ptrs.iter().copied())
+        .chain(iter::once(0))
+        .chain(auxv.into_iter().flat_map(|(ty, val)| [ty, val]))
+        .collect();
+    let sp = (top - words.len() * size_of::<usize>()) & !0xf;
+    for (i, &word) in words.iter().enumerate() {
+        copy_to_user(
+            page_table,
+            (sp + i * size_of::<usize>()) as *mut usize,
+            word,
+        )?;
+    }
+    let argv_base = sp + size_of::<usize>();
+    Ok((
+        sp,
+        argv_base,
+        argv_base + (argv.len() + 1) * size_of::<usize>(),
+    ))
+}
 
-        let pcb = Self {
-            pid,
-            state: State::Ready,
-            kernel_stack,
+impl ProcessControlBlock {
+    //只用于创建initproc, 之后的进程都是fork出来的
+    pub fn initproc(elf_data: &[u8]) -> *mut Self {
+        let (mut mem_set, user_sp, entry) = MemSet::from_elf(elf_data).unwrap();
+        let user_stack_btm = user_sp.floor().0;
+        //initproc还没有运行, 访问用户栈时不能靠缺页处理分配物理页
+        mem_set.handle_page_fault(VirtAddr(user_stack_btm - 1), true, DEFAULT_STACK_LIMIT);
+        let heap_btm = mem_set.heap_start().floor().0;
+        let pcb = Box::leak(Box::new(Self {
+            pid: Pid(pid::ALLOCATOR.lock().alloc()),
+            zombie: false,
+            exiting: false,
             mem_set,
-            trap_ctx_ppn,
-            task_context: TaskContext::goto_trap_return(kernel_stack_btm),
-            base_size: user_stack_btm,
-            heap_btm: user_stack_btm,
-            brk: user_stack_btm,
-            exit_code: 0,
+            threads: Vec::new(),
+            tid_allocator: Allocator::new(),
+            base_size: heap_btm,
+            heap_btm,
+            brk: heap_btm,
+            exit_status: ExitStatus::Exited(0),
             children: vec![],
             parent: core::ptr::null_mut(),
+            wait_queue: WaitQueue::new(),
+            thread_wait_queue: WaitQueue::new(),
             fd_table: vec![Some(stdin()), Some(stdout()), Some(stderr())],
             signal_mask: SignalFlags::empty(),
+            saved_mask: None,
             signal_actions: SignalActions::default(),
-            trap_ctx_backup: trap_ctx,
             signals: SignalFlags::empty(),
             frozen: false,
-            handling_sig: None,
-        };
-        *pcb.trap_ctx() = trap_ctx;
+            fault_addr: 0,
+            stack_rlimit: RLimit::stack(),
+            usage: Usage::default(),
+            children_usage: Usage::default(),
+            itimers: ITimers::default(),
+            mutex_list: Vec::new(),
+            semaphore_list: Vec::new(),
+            condvar_list: Vec::new(),
+            deadlock_detector: DeadlockDetector::default(),
+        }));
+        let tid = pcb.tid_allocator.alloc();
+        let trap_ctx_ppn = pcb.alloc_trap_ctx(tid);
+        let thread = ThreadControlBlock::new(pcb, tid, trap_ctx_ppn, None, SchedInfo::default());
+        let argv = [String::from("initproc")];
+        let (sp, argv_base, envp_base) =
+            push_args(pcb.page_table(), user_stack_btm, entry.0, &argv, &[]).unwrap();
+        *thread.trap_ctx() = TrapContext::new(
+            entry.0,
+            sp,
+            kernel_token(),
+            thread.kernel_stack.btm().0,
+            trap_handler as usize,
+        );
+        let regs = &mut thread.trap_ctx().x;
+        regs[10] = argv.len();
+        regs[11] = argv_base;
+        regs[12] = envp_base;
+        pcb.add_thread(thread);
         pcb
     }
 
-    pub fn fork(&mut self) -> *mut Self {
-        let mem_set = self.mem_set.clone();
-        let trap_ctx_ppn = mem_set.translate(TRAP_CONTEXT_VPN).unwrap().ppn();
-        let pid = pid::ALLOCATOR.exclusive_access().alloc();
-        let kernel_stack = KernelStack::new(pid);
-
-        let kernel_stack_btm = kernel_stack.btm(pid).0;
-        let ret = Box::leak(Box::new(ProcessControlBlock {
-            pid,
-            kernel_stack,
-            task_context: TaskContext::goto_trap_return(kernel_stack_btm),
-            state: State::Ready,
+    //子进程中只有调用fork的线程的副本, 它保留原来的tid
+    pub fn fork(&mut self, thread: &ThreadControlBlock) -> *mut Self {
+        let mut mem_set = self.mem_set.clone();
+        //其他线程的trap上下文和用户栈在子进程中没有用处
+        for other in self
+            .threads
+            .iter()
+            .flatten()
+            .filter(|other| other.tid != thread.tid && !other.is_zombie())
+        {
+            mem_set.remove_area(trap_ctx_vpn(other.tid));
+            if let Some(stack) = other.user_stack {
+                mem_set.remove_area(stack.start);
+            }
+        }
+        let tid = thread.tid;
+        let trap_ctx_ppn = mem_set.translate(trap_ctx_vpn(tid)).unwrap().ppn();
+        let pcb = Box::leak(Box::new(ProcessControlBlock {
+            pid: Pid(pid::ALLOCATOR.lock().alloc()),
+            zombie: false,
+            exiting: false,
             mem_set,
-            trap_ctx_ppn,
+            threads: Vec::new(),
+            tid_allocator: Allocator::with_used(tid),
             base_size: self.base_size,
             heap_btm: self.heap_btm,
             brk: self.brk,
-            exit_code: 0,
+            exit_status: ExitStatus::Exited(0),
             children: Vec::new(),
             parent: self as *mut Self,
+            wait_queue: WaitQueue::new(),
+            thread_wait_queue: WaitQueue::new(),
             fd_table: self.fd_table.clone(),
-            signal_mask: SignalFlags::empty(),
-            signal_actions: Default::default(),
-            trap_ctx_backup: *self.trap_ctx(),
+            //子进程继承屏蔽字
+            signal_mask: self.signal_mask,
+            saved_mask: None,
+            //子进程继承信号的处理方式
+            signal_actions: self.signal_actions,
             signals: SignalFlags::empty(),
             frozen: false,
-            handling_sig: None,
-        })) as *mut Self;
-        unsafe {
-            let ret = &mut *ret;
-            ret.trap_ctx().kernel_sp = kernel_stack_btm;
-        }
+            fault_addr: 0,
+            stack_rlimit: self.stack_rlimit,
+            usage: Usage::default(),
+            children_usage: Usage::default(),
+            itimers: ITimers::default(),
+            mutex_list: Vec::new(),
+            semaphore_list: Vec::new(),
+            condvar_list: Vec::new(),
+            deadlock_detector: DeadlockDetector::default(),
+        }));
+        let child = ThreadControlBlock::new(
+            pcb,
+            tid,
+            trap_ctx_ppn,
+            thread.user_stack,
+            thread.sched.inherit(),
+        );
+        child.trap_ctx().kernel_sp = child.kernel_stack.btm().0;
+        pcb.add_thread(child);
+        let ret = pcb as *mut Self;
         self.children.push(ret);
         ret
     }
 
-    pub fn exec(&mut self, elf_data: &[u8], argv: Vec<String>) {
-        let (mem_set, user_sp, entry) = MemSet::from_elf(elf_data);
-        //得到中断上下文的物理页号
-        let trap_ctx_ppn = mem_set.translate(TRAP_CONTEXT_VPN).unwrap().ppn();
+    //调用者要保证进程中没有其他活着的线程, exec之后调用exec的线程成为0号线程
+    //加载失败时旧的地址空间保持不变
+    pub fn exec(
+        &mut self,
+        thread: &mut ThreadControlBlock,
+        elf_data: &[u8],
+        argv: Vec<String>,
+        envp: Vec<String>,
+    ) -> SyscallResult<()> {
+        //参数太多时要在销毁旧地址空间之前失败
+        if args_size(&argv, &envp) > self.arg_max() {
+            return Err(Errno::E2BIG);
+        }
+        let (mem_set, user_sp, entry) = MemSet::from_elf(elf_data)?;
+        //旧地址空间的物理页(包括共享映射)在这里释放
+        core::mem::replace(&mut self.mem_set, mem_set).recycle();
+        //处理函数在新程序中不存在, 恢复默认动作, 被忽略的信号保持忽略
+        for action in self
+            .signal_actions
+            .iter_mut()
+            .filter(|action| action.handler != SIG_IGN)
+        {
+            *action = SignalAction::default();
+        }
 
-        self.mem_set = mem_set;
-        self.trap_ctx_ppn = trap_ctx_ppn;
+        //丢弃已经退出但还没被回收的线程
+        let current = self.threads[thread.tid].take();
+        self.threads.clear();
+        self.threads.push(current);
+        self.tid_allocator = Allocator::with_used(0);
+        //旧程序的同步原语对新程序没有意义
+        self.mutex_list.clear();
+        self.semaphore_list.clear();
+        self.condvar_list.clear();
+        self.deadlock_detector = DeadlockDetector::default();
+        thread.tid = 0;
+        thread.user_stack = None;
+        thread.trap_ctx_ppn = self.alloc_trap_ctx(0);
 
         let user_stack_btm = user_sp.floor().0;
-        self.base_size = user_stack_btm;
-        self.heap_btm = user_stack_btm;
-        self.brk = user_stack_btm;
-
-        let argc = argv.len();
-        let argv_base = user_stack_btm - size_of::<CStr>() * argc;
-        let page_table = self.page_table();
-        let mut base = argv_base;
-        for (i, arg) in argv.into_iter().enumerate() {
-            let ptr = argv_base + size_of::<CStr>() * i;
-            base = base - arg.len() - 1;
-            *page_table.translate_virt_mut(ptr as *mut CStr) = base as CStr;
-            for (j, c) in arg.bytes().chain(once(b'\0')).enumerate() {
-                *page_table.translate_virt_mut((base + j) as *mut u8) = c;
-            }
-        }
-        base -= base % size_of::<usize>();
+        let heap_btm = self.mem_set.heap_start().floor().0;
+        self.base_size = heap_btm;
+        self.heap_btm = heap_btm;
+        self.brk = heap_btm;
 
-        let kernel_stack_btm = self.kernel_stack.btm(self.pid).0;
-        *self.trap_ctx() = TrapContext::new(
+        let (sp, argv_base, envp_base) =
+            push_args(self.page_table(), user_stack_btm, entry.0, &argv, &envp)?;
+        *thread.trap_ctx() = TrapContext::new(
             entry.0,
-            base,
-            KERNEL_MEM_SPACE.exclusive_access().token(),
-            kernel_stack_btm,
+            sp,
+            kernel_token(),
+            thread.kernel_stack.btm().0,
             trap_handler as usize,
         );
-        let regs = &mut self.trap_ctx().x;
-        regs[10] = argc;
+        //除了栈上的argc, 参数还通过寄存器传给_start
+        let regs = &mut thread.trap_ctx().x;
+        regs[10] = argv.len();
         regs[11] = argv_base;
+        regs[12] = envp_base;
+        Ok(())
+    }
+
+    //exec时参数和环境变量最多能占用的空间, 要给程序本身留下至少1/4的栈, 也不能超过ARG_MAX_LIMIT
+    pub fn arg_max(&self) -> usize {
+        let stack = self.stack_rlimit.cur;
+        (stack / 4)
+            .max(ARG_MAX)
+            .min(stack / 4 * 3)
+            .min(ARG_MAX_LIMIT)
+    }
+
+    //为线程tid分配trap上下文页, 返回它的物理页号
+    pub fn alloc_trap_ctx(&mut self, tid: usize) -> PhysPageNum {
+        let vpn = trap_ctx_vpn(tid);
+        self.mem_set
+            .insert_framed_area((vpn..vpn + 1usize).into(), Permission::R | Permission::W);
+        self.mem_set.translate(vpn).unwrap().ppn()
+    }
+
+    //为thread_create创建的线程tid分配用户栈
+    pub fn alloc_user_stack(&mut self, tid: usize) -> VirtPageSpan {
+        let span = user_stack_span(tid);
+        self.mem_set
+            .insert_lazy_area(span, Permission::R | Permission::W | Permission::U);
+        span
+    }
+
+    //把线程放到threads中下标为tid的位置, 返回它的指针
+    pub fn add_thread(&mut self, mut thread: Box<ThreadControlBlock>) -> *mut ThreadControlBlock {
+        let tid = thread.tid;
+        if self.threads.len() <= tid {
+            self.threads.resize_with(tid + 1, || None);
+        }
+        let ptr = thread.as_mut() as *mut _;
+        self.threads[tid] = Some(thread);
+        ptr
+    }
+
+    pub fn thread(&mut self, tid: usize) -> Option<&mut ThreadControlBlock> {
+        self.threads
+            .get_mut(tid)
+            .and_then(|thread| thread.as_deref_mut())
+    }
+
+    //线程退出后立即释放它的trap上下文和用户栈, 线程控制块留给waittid回收
+    pub fn release_thread(&mut self, thread: &mut ThreadControlBlock) {
+        self.mem_set.remove_area(trap_ctx_vpn(thread.tid));
+        if let Some(stack) = thread.user_stack.take() {
+            self.mem_set.remove_area(stack.start);
+        }
+    }
+
+    //唤醒所有阻塞的线程, 让它们去处理信号或者退出
+    pub fn interrupt(&mut self) {
+        for thread in self.threads.iter_mut().flatten() {
+            wakeup(thread.as_mut());
+        }
+    }
+
+    //阻塞的系统调用据此判断是否应该提前返回EINTR
+    pub fn interrupted(&self) -> bool {
+        self.exiting || self.has_pending_signal()
+    }
+
+    //最后一个线程退出时调用
+    pub fn exit(&mut self) {
+        self.zombie = true;
+        self.recycle();
+        if !self.parent.is_null() {
+            let parent = unsafe { &mut *self.parent };
+            //父进程不关心退出状态, 不留下僵尸进程
+            if parent.reaps_children() {
+                let this = self as *mut Self;
+                parent.children.retain(|&child| child != this);
+                self.parent = core::ptr::null_mut();
+            }
+            //唤醒可能阻塞在waitpid上的父进程
+            parent.wait_queue.wake_all();
+            parent.raise(SignalFlags::SIGCHLD);
+        }
+    }
+
+    //SIGCHLD被忽略或者设置了SA_NOCLDWAIT时, 子进程退出后由内核直接回收
+    fn reaps_children(&self) -> bool {
+        let action = &self.signal_actions[SIGCHLD as usize];
+        action.handler == SIG_IGN || action.flags.contains(SigActionFlags::NOCLDWAIT)
     }
 
     pub fn token(&self) -> usize {
@@ -207,54 +487,67 @@ impl ProcessControlBlock {
         TopLevelEntry::from_token(self.token())
     }
 
-    pub fn trap_ctx(&self) -> &'static mut TrapContext {
-        self.trap_ctx_ppn.read_as()
-    }
-
     pub fn pid(&self) -> Pid {
         self.pid
     }
 
     pub fn is_zombie(&self) -> bool {
-        self.state == State::Zombie
+        self.zombie
     }
 
     pub fn recycle(&mut self) {
-        let initproc = INITPROC.exclusive_access();
+        let initproc = INITPROC.get();
+        let reap = initproc.reaps_children();
+        let mut has_zombie = false;
         for &child in self.children.iter() {
             unsafe {
+                //僵尸进程已经不在任何hart上运行, 可以直接释放
+                if reap && (*child).is_zombie() {
+                    core::ptr::drop_in_place(child);
+                    continue;
+                }
                 (*child).parent = initproc as *mut _;
                 initproc.children.push(child);
+                has_zombie |= (*child).is_zombie();
             }
         }
+        //过继来的子进程中已经有僵尸进程了, 需要让initproc回收它们
+        if has_zombie {
+            initproc.wait_queue.wake_all();
+            initproc.raise(SignalFlags::SIGCHLD);
+        }
         self.children.clear();
         self.mem_set.recycle();
         self.fd_table.clear();
     }
 
-    //改变堆顶, 成功时返回旧的堆顶, 失败时返回usize::MAX
-    pub fn change_brk(&mut self, size: isize) -> usize {
+    //改变堆顶, 成功时返回旧的堆顶
+    pub fn change_brk(&mut self, size: isize) -> SyscallResult {
         //如果申请的内存不是页对齐的, 则返回错误
         if size as usize & PAGE_MASK != 0 {
-            return usize::MAX;
+            return Err(Errno::EINVAL);
         }
         let old = self.brk;
         let new = (self.brk as isize + size) as usize;
         //如果堆顶超过了堆底, 则返回错误
         if new < self.heap_btm {
-            return usize::MAX;
+            return Err(Errno::EINVAL);
+        }
+        //堆不能长进mmap区域
+        if new > MMAP_BASE {
+            return Err(Errno::ENOMEM);
         }
         let old_ppn = VirtAddr(old).floor();
         let new_ppn = VirtAddr(new).floor();
         if old_ppn == new_ppn {
-            return old;
+            return Ok(old);
         } else if old_ppn < new_ppn {
             self.mem_set.heap_grow(new_ppn);
         } else {
             self.mem_set.heap_shrink(new_ppn);
         }
         self.brk = new;
-        old
+        Ok(old)
     }
 
     // 添加fd表项
@@ -274,16 +567,16 @@ impl ProcessControlBlock {
         }
     }
 
-    pub fn close_fd(&mut self, fd: usize) -> isize {
+    pub fn close_fd(&mut self, fd: usize) -> SyscallResult {
         if let Some(entry) = self.fd_table.get_mut(fd) {
             if entry.is_none() {
-                -1
+                Err(Errno::EBADF)
             } else {
                 *entry = None;
-                0
+                Ok(0)
             }
         } else {
-            -1
+            Err(Errno::EBADF)
         }
     }
 
@@ -295,14 +588,106 @@ impl ProcessControlBlock {
         }
     }
 
+    //信号没有被屏蔽, 正在执行的信号处理函数的屏蔽字也在signal_mask中
+    fn is_deliverable(&self, signal: SignalFlags) -> bool {
+        !self.signal_mask.contains(signal)
+    }
+
+    //处理方式是SIG_IGN, 或者SIG_DFL而默认动作就是忽略
+    //同步信号忽略之后会反复触发, 不能被忽略
+    fn is_ignored(&self, signal: SignalFlags) -> bool {
+        if signal.intersects(SignalFlags::HANDLE_BY_KERNEL | SignalFlags::SYNCHRONOUS) {
+            return false;
+        }
+        match self.signal_actions[signal.code()].handler {
+            SIG_IGN => true,
+            SIG_DFL => signal.intersects(SignalFlags::IGNORED_BY_DEFAULT),
+            _ => false,
+        }
+    }
+
+    //向进程发送信号, 没有被屏蔽的忽略信号直接丢弃
+    pub fn raise(&mut self, signal: SignalFlags) {
+        if self.is_deliverable(signal) && self.is_ignored(signal) {
+            return;
+        }
+        self.signals.insert(signal);
+        //打断阻塞中的线程, 让它们有机会处理信号
+        self.interrupt();
+    }
+
+    //按默认动作终止进程的信号: 没有处理函数的, 以及被屏蔽的同步信号
+    fn fatal_signals(&self) -> SignalFlags {
+        self.signals
+            .iter()
+            .filter(|&signal| {
+                if signal.intersects(SignalFlags::UNBLOCKABLE) || self.is_deliverable(signal) {
+                    match self.signal_actions[signal.code()].handler {
+                        SIG_DFL => true,
+                        SIG_IGN => signal.intersects(SignalFlags::SYNCHRONOUS),
+                        _ => false,
+                    }
+                } else {
+                    signal.intersects(SignalFlags::SYNCHRONOUS)
+                }
+            })
+            .collect()
+    }
+
+    //在用户栈上压入信号帧, 返回用户态后从处理函数开始执行
+    //处理函数的参数是(信号, &SigInfo, &SignalFrame), 返回时跳到sigreturn跳板
+    fn push_signal_frame(&mut self, signal: SignalFlags, handler: usize) {
+        let code = signal.code();
+        let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+        let cx = thread.trap_ctx();
+        let frame = SignalFrame {
+            info: SigInfo {
+                signo: code as i32,
+                addr: if signal == SignalFlags::SIGSEGV {
+                    self.fault_addr
+                } else {
+                    0
+                },
+                ..Default::default()
+            },
+            x: cx.x,
+            sepc: cx.sepc,
+            mask: self.saved_mask.take().unwrap_or(self.signal_mask),
+        };
+        let sp = cx.x[2].wrapping_sub(size_of::<SignalFrame>()) & !0xf;
+        if copy_to_user(self.page_table(), sp as *mut SignalFrame, frame).is_err() {
+            error!(
+                "[signal-handler] process {} cannot push a signal frame at {:#x}",
+                self.pid.0, sp
+            );
+            PROCESSOR
+                .exclusive_access()
+                .exit_group(ExitStatus::Signaled(SIGSEGV))
+                .schedule();
+        }
+        //处理函数执行期间屏蔽信号本身和sigaction指定的信号, sigreturn时恢复
+        self.signal_mask |= (self.signal_actions[code].mask | signal) - SignalFlags::UNBLOCKABLE;
+        cx.x[1] = SIGRETURN_VA;
+        cx.x[2] = sp;
+        cx.x[10] = code;
+        cx.x[11] = sp;
+        cx.x[12] = sp;
+        cx.sepc = handler;
+    }
+
+    //阻塞在内核中的进程据此判断自己是否被信号打断
+    pub fn has_pending_signal(&self) -> bool {
+        self.signals
+            .iter()
+            .any(|signal| self.is_deliverable(signal) && !self.is_ignored(signal))
+    }
+
     fn solve_pending_signals(&mut self) {
         for (name, signal) in self.signals.iter_names() {
-            if !self.signal_mask.contains(signal)
-                && self.handling_sig.map_or(true, |handling| {
-                    !self.signal_actions[handling].mask.contains(signal)
-                })
-            {
-                if signal.contains(SignalFlags::HANDLE_BY_KERNEL) {
+            if self.is_deliverable(signal) {
+                if self.is_ignored(signal) {
+                    self.signals -= signal;
+                } else if signal.contains(SignalFlags::HANDLE_BY_KERNEL) {
                     match signal {
                         SignalFlags::SIGSTOP => {
                             self.frozen = true;
@@ -318,25 +703,29 @@ impl ProcessControlBlock {
                                 "[signal-handler] process {} is killed by signal {}",
                                 pid, name
                             );
-                            PROCESSOR.exclusive_access().exit_current(-1).schedule();
+                            PROCESSOR
+                                .exclusive_access()
+                                .exit_group(ExitStatus::Signaled(signal.code() as Signal))
+                                .schedule();
                         }
                     }
                 } else {
                     let code = signal.code();
                     match self.signal_actions[code].handler {
-                        0 => {
+                        SIG_DFL => {
                             let pid = self.pid.0;
                             error!(
                                 "[signal-handler] process {} is killed by signal {}",
                                 pid, name
                             );
-                            PROCESSOR.exclusive_access().exit_current(-1).schedule();
+                            PROCESSOR
+                                .exclusive_access()
+                                .exit_group(ExitStatus::Signaled(code as Signal))
+                                .schedule();
                         }
                         handler => {
-                            self.handling_sig = Some(code);
                             self.signals &= !signal;
-                            self.trap_ctx_backup = *self.trap_ctx();
-                            self.trap_ctx().sepc = handler;
+                            self.push_signal_frame(signal, handler);
                             return;
                         }
                     };
@@ -345,16 +734,38 @@ impl ProcessControlBlock {
         }
     }
 
-    pub fn handle_signals(&mut self) {
-        if let Some((exit_code, sig)) = self.signals.check_error() {
-            let pid = self.pid.0;
+    //访问addr处的内存出错
+    pub fn segv(&mut self, addr: usize) {
+        if self
+            .mem_set
+            .is_stack_overflow(VirtAddr(addr), self.stack_rlimit.cur)
+        {
             error!(
-                "[signal-handler] process {} is killed by signal {}",
-                pid, sig
+                "[kernel] process {} overflowed its user stack at {:#x}, stack limit is {:#x} bytes",
+                self.pid.0, addr, self.stack_rlimit.cur
             );
+        }
+        self.fault_addr = addr;
+        self.signals.insert(SignalFlags::SIGSEGV);
+    }
+
+    pub fn handle_signals(&mut self) {
+        if let Some((signal, sig)) = self.fatal_signals().check_error() {
+            let pid = self.pid.0;
+            if signal == SIGSEGV {
+                error!(
+                    "[signal-handler] process {} is killed by signal {} at {:#x}",
+                    pid, sig, self.fault_addr
+                );
+            } else {
+                error!(
+                    "[signal-handler] process {} is killed by signal {}",
+                    pid, sig
+                );
+            }
             PROCESSOR
                 .exclusive_access()
-                .exit_current(exit_code)
+                .exit_group(ExitStatus::Signaled(signal))
                 .schedule();
         }
         loop {
@@ -364,5 +775,9 @@ impl ProcessControlBlock {
             }
             PROCESSOR.exclusive_access().suspend_current().schedule();
         }
+        //sigsuspend等到的信号没有调用处理函数
+        if let Some(mask) = self.saved_mask.take() {
+            self.signal_mask = mask;
+        }
     }
 }
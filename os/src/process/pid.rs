@@ -1,4 +1,4 @@
-use crate::sync::up::UPSafeCell;
+use crate::sync::spin::SpinLock;
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use core::{
@@ -63,48 +63,57 @@ impl Sub for Pid {
     }
 }
 
+// 可回收的编号分配器, pid, tid和内核栈编号都用它分配
 pub struct Allocator {
-    current: Pid,
-    pool: Vec<Pid>,
+    current: usize,
+    pool: Vec<usize>,
 }
 
 impl Allocator {
     pub fn new() -> Self {
         Self {
-            current: Pid(0),
+            current: 0,
             pool: Vec::new(),
         }
     }
 
-    pub fn alloc(&mut self) -> Pid {
-        if let Some(pid) = self.pool.pop() {
-            pid
+    // 编号id已经被占用, 比它小的编号都是空闲的
+    pub fn with_used(id: usize) -> Self {
+        Self {
+            current: id + 1,
+            pool: (0..id).collect(),
+        }
+    }
+
+    pub fn alloc(&mut self) -> usize {
+        if let Some(id) = self.pool.pop() {
+            id
         } else {
-            let pid = self.current;
+            let id = self.current;
             self.current += 1;
-            pid
+            id
         }
     }
 
-    pub fn dealloc(&mut self, pid: Pid) {
-        self.pool.push(pid);
+    pub fn dealloc(&mut self, id: usize) {
+        self.pool.push(id);
     }
 }
 
 lazy_static! {
-    pub static ref ALLOCATOR: UPSafeCell<Allocator> = unsafe { UPSafeCell::new(Allocator::new()) };
-    pub static ref PID2TASK: UPSafeCell<BTreeMap<Pid, *mut ProcessControlBlock>> =
-        unsafe { UPSafeCell::new(BTreeMap::new()) };
+    pub static ref ALLOCATOR: SpinLock<Allocator> = SpinLock::new(Allocator::new());
+    pub static ref PID2TASK: SpinLock<BTreeMap<Pid, *mut ProcessControlBlock>> =
+        SpinLock::new(BTreeMap::new());
 }
 
 pub fn task_find(pid: impl Into<Pid>) -> Option<*mut ProcessControlBlock> {
-    PID2TASK.exclusive_access().get(&pid.into()).copied()
+    PID2TASK.lock().get(&pid.into()).copied()
 }
 
 pub fn task_insert(pid: impl Into<Pid>, task: *mut ProcessControlBlock) {
-    PID2TASK.exclusive_access().insert(pid.into(), task);
+    PID2TASK.lock().insert(pid.into(), task);
 }
 
 pub fn task_delete(pid: impl Into<Pid>) {
-    PID2TASK.exclusive_access().remove(&pid.into());
+    PID2TASK.lock().remove(&pid.into());
 }
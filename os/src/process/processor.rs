@@ -1,17 +1,26 @@
+use core::arch::asm;
+use core::hint::spin_loop;
 
-use crate::sync::up::UPSafeCell;
-use crate::timer::set_next_trigger;
+use crate::mm::address::VirtPageNum;
+use crate::sync::{per_hart::PerHart, spin::KERNEL_LOCK};
+use crate::timer::{check_timers, get_time, set_next_trigger};
 use crate::trap::context::Context as TrapContext;
 
 use super::context::Context as TaskContext;
 
-use super::pcb::{ProcessControlBlock, State};
+use super::pcb::{ExitStatus, ProcessControlBlock};
 use super::queue::QUEUE;
 use super::switch::__switch;
+use super::tcb::{State, ThreadControlBlock};
 
 pub struct Processor {
-    current: *mut ProcessControlBlock,
+    //当前运行的线程
+    current: *mut ThreadControlBlock,
     idle_task_ctx: TaskContext,
+    //上一次在用户态和内核态之间切换或者切换线程的时刻, 用于统计进程的运行时间
+    stamp: usize,
+    //trap_enter时还没有拿到大内核锁, 先记下在用户态运行的时间
+    user_elapsed: usize,
 }
 
 unsafe impl Send for Processor {}
@@ -22,16 +31,18 @@ impl Processor {
         Self {
             current: core::ptr::null_mut(),
             idle_task_ctx: TaskContext::idle(),
+            stamp: 0,
+            user_elapsed: 0,
         }
     }
 
-    pub fn take(&mut self) -> *mut ProcessControlBlock {
+    pub fn take(&mut self) -> *mut ThreadControlBlock {
         let ret = self.current;
         self.current = core::ptr::null_mut();
         ret
     }
 
-    pub fn current(&mut self) -> Option<&mut ProcessControlBlock> {
+    pub fn current_thread(&mut self) -> Option<&'static mut ThreadControlBlock> {
         if self.current == core::ptr::null_mut() {
             None
         } else {
@@ -39,52 +50,169 @@ impl Processor {
         }
     }
 
+    // 当前线程所属的进程
+    pub fn current(&mut self) -> Option<&'static mut ProcessControlBlock> {
+        self.current_thread().map(|t| t.process())
+    }
+
     pub fn current_token(&mut self) -> Option<usize> {
         self.current().map(|c| c.token())
     }
 
     pub fn current_trap_ctx(&mut self) -> Option<&'static mut TrapContext> {
-        self.current().map(|c| c.trap_ctx())
+        self.current_thread().map(|t| t.trap_ctx())
     }
 
     pub fn idle_task_ctx(&mut self) -> *mut TaskContext {
         &mut self.idle_task_ctx as *mut _
     }
 
+    // 把上次记录以来的时间计入当前进程的用户态或内核态时间
+    fn account(&mut self, user: bool) {
+        let now = get_time();
+        let elapsed = now - self.stamp;
+        self.stamp = now;
+        self.charge(elapsed, user);
+    }
+
+    // 要持有大内核锁, 会修改进程的统计信息和信号
+    fn charge(&mut self, elapsed: usize, user: bool) {
+        if let Some(task) = self.current() {
+            if user {
+                task.usage.utime += elapsed;
+            } else {
+                task.usage.stime += elapsed;
+            }
+            for signal in task.itimers.consume(elapsed, user).iter() {
+                task.raise(signal);
+            }
+        }
+    }
+
+    // 从用户态陷入内核时在获取大内核锁之前调用, 之前的时间都在用户态, 等锁的时间算作内核态
+    pub fn trap_enter(&mut self) {
+        let now = get_time();
+        self.user_elapsed = now - self.stamp;
+        self.stamp = now;
+    }
+
+    // 拿到大内核锁之后调用, 把trap_enter记下的用户态时间计入当前进程
+    pub fn account_user(&mut self) {
+        let elapsed = core::mem::take(&mut self.user_elapsed);
+        self.charge(elapsed, true);
+    }
+
+    // 返回用户态前调用, 之前的时间都在内核态
+    pub fn trap_exit(&mut self) {
+        self.account(false)
+    }
+
+    // 每个hart的idle控制流, 持有大内核锁进入, 切换到的进程返回用户态时才释放
     pub fn run_tasks(&mut self) {
+        KERNEL_LOCK.acquire();
         loop {
-            if let Some(task) = QUEUE.exclusive_access().fetch() {
+            //不能在if let中直接取, 否则锁会一直持有到切换回来之后
+            let task = QUEUE.lock().fetch();
+            if let Some(task) = task {
                 self.current = task;
                 let idle_task_ctx = self.idle_task_ctx();
-                let task_ctx = self.current().unwrap().task_ctx();
-                self.current().unwrap().state = State::Running;
-                set_next_trigger();
+                let task_ctx = self.current_thread().unwrap().task_ctx();
+                self.current_thread().unwrap().state = State::Running;
+                //内核栈的虚拟地址会被新线程复用, 不能用别的hart上留下的旧映射
+                unsafe { asm!("sfence.vma") };
+                let slice = QUEUE.lock().time_slice(unsafe { &*task });
+                set_next_trigger(slice);
+                //idle的时间不算在任何进程上
+                self.stamp = get_time();
                 unsafe { __switch(idle_task_ctx, task_ctx) }
+                //没有父进程等待的僵尸进程, 只有在最后一个线程离开它的内核栈之后才能释放
+                let process = unsafe { (*task).process() };
+                if process.is_zombie() && process.parent.is_null() {
+                    unsafe { core::ptr::drop_in_place(process) };
+                }
+            } else {
+                //内核态下不响应时钟中断, 没有就绪进程时在这里检查睡眠的进程是否到期
+                check_timers();
+                //让出大内核锁, 其他hart才能进入内核
+                KERNEL_LOCK.release();
+                spin_loop();
+                KERNEL_LOCK.acquire();
             }
         }
     }
 
     pub fn suspend_current(&mut self) -> &mut Self {
-        self.current().unwrap().state = State::Ready;
-        QUEUE.exclusive_access().push(self.current);
+        self.current_thread().unwrap().state = State::Ready;
+        self.current().unwrap().usage.voluntary_switches += 1;
+        QUEUE.lock().push(self.current);
         self
     }
 
+    // 时间片用完, 交给调度器决定怎么放回就绪队列
+    pub fn preempt_current(&mut self) -> &mut Self {
+        self.current_thread().unwrap().state = State::Ready;
+        self.current().unwrap().usage.involuntary_switches += 1;
+        QUEUE.lock().preempt(self.current);
+        self
+    }
+
+    // 阻塞当前线程, 由等待队列负责在之后唤醒它
+    pub fn block_current(&mut self) -> &mut Self {
+        self.current_thread().unwrap().state = State::Blocked;
+        self.current().unwrap().usage.voluntary_switches += 1;
+        self
+    }
+
+    // 退出当前线程, 最后一个线程退出时整个进程退出
     pub fn exit_current(&mut self, code: i32) -> &mut Self {
-        let cur = self.current().unwrap();
-        cur.state = State::Zombie;
-        cur.exit_code = code;
-        cur.recycle();
+        let thread = self.current_thread().unwrap();
+        thread.state = State::Zombie;
+        thread.exit_code = code;
+        let process = thread.process();
+        //唤醒可能阻塞在waittid上的线程
+        process.thread_wait_queue.wake_all();
+        if process.threads.iter().flatten().all(|t| t.is_zombie()) {
+            if !process.exiting {
+                process.exit_status = ExitStatus::Exited(code);
+            }
+            process.exit();
+        } else {
+            process.release_thread(thread);
+        }
         self
     }
 
+    // 退出整个进程, 其他线程在回到用户态之前退出
+    pub fn exit_group(&mut self, status: ExitStatus) -> &mut Self {
+        let process = self.current().unwrap();
+        if !process.exiting {
+            process.exiting = true;
+            process.exit_status = status;
+            process.interrupt();
+        }
+        self.exit_current(process.exit_status.code())
+    }
+
     pub fn schedule(&mut self) {
+        self.account(false);
         let idle_task_ctx = self.idle_task_ctx();
-        let switch_task_ctx = self.current().unwrap().task_ctx();
+        let switch_task_ctx = self.current_thread().unwrap().task_ctx();
         unsafe { __switch(switch_task_ctx, idle_task_ctx) }
     }
 }
 
+// 内核访问当前进程的用户内存前调用, 按vma补上缺页, token不是当前进程的时什么也不做
+pub fn fault_in_current(token: usize, vpn: VirtPageNum, write: bool) -> bool {
+    match PROCESSOR.exclusive_access().current() {
+        Some(task) if task.token() == token => {
+            let stack_limit = task.stack_rlimit.cur;
+            task.mem_set
+                .handle_page_fault(vpn.floor(), write, stack_limit)
+        }
+        _ => false,
+    }
+}
+
 lazy_static! {
-    pub static ref PROCESSOR: UPSafeCell<Processor> = unsafe { UPSafeCell::new(Processor::new()) };
+    pub static ref PROCESSOR: PerHart<Processor> = PerHart::new(Processor::new);
 }
@@ -0,0 +1,76 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+use crate::{
+    process::tcb::ThreadControlBlock,
+    timer::{get_time, TIME_SLICE},
+};
+
+use super::{Scheduler, NICE_MAX};
+
+const LEVELS: usize = 4;
+// 每隔一段时间把所有进程提升回初始级别, 防止低级别的进程饿死
+const BOOST_PERIOD: usize = 100 * TIME_SLICE;
+
+// 正的nice值让进程从更低的级别开始
+fn base_level(nice: isize) -> usize {
+    if nice > 0 {
+        nice as usize * LEVELS / (NICE_MAX as usize + 1)
+    } else {
+        0
+    }
+}
+
+// 多级反馈队列, 用完时间片的进程降到下一级, 级别越低时间片越长
+pub struct MultiLevelFeedback {
+    queues: [VecDeque<*mut ThreadControlBlock>; LEVELS],
+    last_boost: usize,
+}
+
+impl MultiLevelFeedback {
+    pub fn new() -> Self {
+        Self {
+            queues: Default::default(),
+            last_boost: 0,
+        }
+    }
+
+    fn boost(&mut self) {
+        let tasks: Vec<_> = self
+            .queues
+            .iter_mut()
+            .flat_map(|queue| queue.drain(..))
+            .collect();
+        for task in tasks {
+            let sched = unsafe { &mut (*task).sched };
+            sched.level = base_level(sched.nice);
+            self.queues[sched.level].push_back(task);
+        }
+    }
+}
+
+impl Scheduler for MultiLevelFeedback {
+    fn push(&mut self, task: *mut ThreadControlBlock) {
+        let sched = unsafe { &mut (*task).sched };
+        sched.level = sched.level.max(base_level(sched.nice));
+        self.queues[sched.level].push_back(task);
+    }
+
+    fn fetch(&mut self) -> Option<*mut ThreadControlBlock> {
+        let now = get_time();
+        if now - self.last_boost >= BOOST_PERIOD {
+            self.last_boost = now;
+            self.boost();
+        }
+        self.queues.iter_mut().find_map(|queue| queue.pop_front())
+    }
+
+    fn preempt(&mut self, task: *mut ThreadControlBlock) {
+        let sched = unsafe { &mut (*task).sched };
+        sched.level = (sched.level + 1).min(LEVELS - 1);
+        self.push(task);
+    }
+
+    fn time_slice(&self, task: &ThreadControlBlock) -> usize {
+        TIME_SLICE << task.sched.level
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(feature = "sched_mlfq")]
+mod mlfq;
+#[cfg(not(any(feature = "sched_stride", feature = "sched_mlfq")))]
+mod rr;
+#[cfg(all(feature = "sched_stride", not(feature = "sched_mlfq")))]
+mod stride;
+
+use crate::{sync::spin::SpinLock, timer::TIME_SLICE};
+
+use super::tcb::ThreadControlBlock;
+
+// nice值的范围, 与linux一致, 越小优先级越高
+pub const NICE_MIN: isize = -20;
+pub const NICE_MAX: isize = 19;
+
+// 调度算法需要的每个进程的信息
+#[derive(Clone, Copy, Default)]
+pub struct SchedInfo {
+    pub nice: isize,
+    // stride调度中进程已经走过的路程
+    pub pass: usize,
+    // 多级反馈队列中进程所在的级别
+    pub level: usize,
+}
+
+impl SchedInfo {
+    // fork时只继承nice值
+    pub fn inherit(&self) -> Self {
+        Self {
+            nice: self.nice,
+            ..Default::default()
+        }
+    }
+}
+
+pub trait Scheduler {
+    // 加入一个就绪的进程
+    fn push(&mut self, task: *mut ThreadControlBlock);
+    // 取出下一个要运行的进程
+    fn fetch(&mut self) -> Option<*mut ThreadControlBlock>;
+    // 进程用完时间片被抢占, 默认和主动让出一样处理
+    fn preempt(&mut self, task: *mut ThreadControlBlock) {
+        self.push(task)
+    }
+    // 进程这次运行的时间片, 以时钟周期计
+    fn time_slice(&self, _task: &ThreadControlBlock) -> usize {
+        TIME_SLICE
+    }
+}
+
+// 编译时通过feature选择调度算法, 默认为时间片轮转
+#[cfg(feature = "sched_mlfq")]
+type Policy = mlfq::MultiLevelFeedback;
+#[cfg(all(feature = "sched_stride", not(feature = "sched_mlfq")))]
+type Policy = stride::Stride;
+#[cfg(not(any(feature = "sched_stride", feature = "sched_mlfq")))]
+type Policy = rr::RoundRobin;
+
+pub struct Queue {
+    scheduler: Policy,
+}
+
+unsafe impl Sync for Queue {}
+unsafe impl Send for Queue {}
+
+impl Queue {
+    pub fn new() -> Self {
+        Self {
+            scheduler: Policy::new(),
+        }
+    }
+
+    pub fn push(&mut self, task: *mut ThreadControlBlock) {
+        self.scheduler.push(task);
+    }
+
+    pub fn fetch(&mut self) -> Option<*mut ThreadControlBlock> {
+        self.scheduler.fetch()
+    }
+
+    pub fn preempt(&mut self, task: *mut ThreadControlBlock) {
+        self.scheduler.preempt(task);
+    }
+
+    pub fn time_slice(&self, task: &ThreadControlBlock) -> usize {
+        self.scheduler.time_slice(task)
+    }
+}
+
+lazy_static! {
+    pub static ref QUEUE: SpinLock<Queue> = SpinLock::new(Queue::new());
+}
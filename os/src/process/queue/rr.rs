@@ -0,0 +1,28 @@
+use alloc::collections::VecDeque;
+
+use crate::process::tcb::ThreadControlBlock;
+
+use super::Scheduler;
+
+// 时间片轮转, 不考虑nice值
+pub struct RoundRobin {
+    queue: VecDeque<*mut ThreadControlBlock>,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler for RoundRobin {
+    fn push(&mut self, task: *mut ThreadControlBlock) {
+        self.queue.push_back(task);
+    }
+
+    fn fetch(&mut self) -> Option<*mut ThreadControlBlock> {
+        self.queue.pop_front()
+    }
+}
@@ -0,0 +1,52 @@
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use crate::process::tcb::ThreadControlBlock;
+
+use super::{Scheduler, NICE_MIN};
+
+const BIG_STRIDE: usize = 1 << 32;
+
+// linux中nice值到权重的映射, 相邻两级的cpu时间大约相差10%
+const NICE_TO_WEIGHT: [usize; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+    3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+    70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+fn stride(nice: isize) -> usize {
+    BIG_STRIDE / NICE_TO_WEIGHT[(nice - NICE_MIN) as usize]
+}
+
+// 每次选择pass最小的进程运行, 运行后pass增加与权重成反比的步长
+pub struct Stride {
+    heap: BinaryHeap<Reverse<(usize, *mut ThreadControlBlock)>>,
+    // 最近一次被选中的进程的pass
+    min_pass: usize,
+}
+
+impl Stride {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            min_pass: 0,
+        }
+    }
+}
+
+impl Scheduler for Stride {
+    fn push(&mut self, task: *mut ThreadControlBlock) {
+        let sched = unsafe { &mut (*task).sched };
+        //新建或者睡眠很久的进程pass太小, 不能让它独占cpu
+        sched.pass = sched.pass.max(self.min_pass);
+        self.heap.push(Reverse((sched.pass, task)));
+    }
+
+    fn fetch(&mut self) -> Option<*mut ThreadControlBlock> {
+        let Reverse((pass, task)) = self.heap.pop()?;
+        self.min_pass = pass;
+        let sched = unsafe { &mut (*task).sched };
+        sched.pass = pass + stride(sched.nice);
+        Some(task)
+    }
+}
@@ -0,0 +1,21 @@
+use crate::constant::{DEFAULT_STACK_LIMIT, MAX_STACK_LIMIT};
+
+// 目前只支持限制主线程用户栈的大小
+pub const RLIMIT_STACK: usize = 3;
+
+// 和linux的struct rlimit布局相同, 单位是字节
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RLimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+impl RLimit {
+    pub fn stack() -> Self {
+        Self {
+            cur: DEFAULT_STACK_LIMIT,
+            max: MAX_STACK_LIMIT,
+        }
+    }
+}
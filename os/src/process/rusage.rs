@@ -0,0 +1,95 @@
+use core::ops::AddAssign;
+
+use crate::{constant::CLOCK_FREQ, timer::TimeVal};
+
+// getrusage的who, 不支持RUSAGE_THREAD
+pub const RUSAGE_SELF: isize = 0;
+pub const RUSAGE_CHILDREN: isize = -1;
+
+// times返回的时间以1/CLK_TCK秒为单位
+const CLK_TCK: usize = 100;
+
+// 进程的资源使用情况, 时间以时钟周期计
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    pub utime: usize,
+    pub stime: usize,
+    // 缺页异常的次数
+    pub page_faults: usize,
+    // 因为阻塞或者yield主动让出CPU的次数
+    pub voluntary_switches: usize,
+    // 时间片用完被抢占的次数
+    pub involuntary_switches: usize,
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.utime += rhs.utime;
+        self.stime += rhs.stime;
+        self.page_faults += rhs.page_faults;
+        self.voluntary_switches += rhs.voluntary_switches;
+        self.involuntary_switches += rhs.involuntary_switches;
+    }
+}
+
+// 和linux的struct rusage布局相同, 没有统计的项为0
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RUsage {
+    pub utime: TimeVal,
+    pub stime: TimeVal,
+    pub maxrss: usize,
+    pub ixrss: usize,
+    pub idrss: usize,
+    pub isrss: usize,
+    pub minflt: usize,
+    pub majflt: usize,
+    pub nswap: usize,
+    pub inblock: usize,
+    pub oublock: usize,
+    pub msgsnd: usize,
+    pub msgrcv: usize,
+    pub nsignals: usize,
+    pub nvcsw: usize,
+    pub nivcsw: usize,
+}
+
+impl From<Usage> for RUsage {
+    fn from(usage: Usage) -> Self {
+        Self {
+            utime: TimeVal::from_ticks(usage.utime),
+            stime: TimeVal::from_ticks(usage.stime),
+            //没有交换区, 所有缺页都不需要读磁盘
+            minflt: usage.page_faults,
+            nvcsw: usage.voluntary_switches,
+            nivcsw: usage.involuntary_switches,
+            ..Default::default()
+        }
+    }
+}
+
+// 和linux的struct tms布局相同
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+impl Tms {
+    pub fn new(usage: &Usage, children: &Usage) -> Self {
+        Self {
+            utime: clock_ticks(usage.utime),
+            stime: clock_ticks(usage.stime),
+            cutime: clock_ticks(children.utime),
+            cstime: clock_ticks(children.stime),
+        }
+    }
+}
+
+// 时钟周期数转换成times使用的时钟滴答数
+pub fn clock_ticks(cycles: usize) -> usize {
+    cycles / (CLOCK_FREQ / CLK_TCK)
+}
@@ -42,6 +42,10 @@ pub const SIGSYS: Signal = 31;
 
 pub const MAX_SIG: usize = 31;
 
+// SignalAction::handler的两个特殊值
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
 bitflags! {
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct SignalFlags: i32 {
@@ -79,6 +83,20 @@ bitflags! {
         const SIGSYS = 1 << 31;
 
         const HANDLE_BY_KERNEL = SIGKILL | SIGSTOP | SIGCONT | SIGDEF;
+        // 由当前指令引发的信号, 被屏蔽时处理函数返回后会再次触发, 只能终止进程
+        const SYNCHRONOUS = SIGILL | SIGBUS | SIGFPE | SIGSEGV;
+        // 不能被屏蔽
+        const UNBLOCKABLE = SIGKILL | SIGSTOP;
+        // 默认动作是忽略
+        const IGNORED_BY_DEFAULT = SIGCHLD | SIGURG | SIGWINCH;
+    }
+}
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct SigActionFlags: u32 {
+        // 只对SIGCHLD有效, 子进程退出后直接回收, 不留下僵尸进程
+        const NOCLDWAIT = 1 << 1;
     }
 }
 
@@ -93,19 +111,19 @@ impl SignalFlags {
         self.bits().trailing_zeros() as usize
     }
 
-    pub fn check_error(&self) -> Option<(i32, &'static str)> {
+    pub fn check_error(&self) -> Option<(Signal, &'static str)> {
         if self.contains(Self::SIGINT) {
-            Some((-2, "SIGINT"))
+            Some((SIGINT, "SIGINT"))
         } else if self.contains(Self::SIGILL) {
-            Some((-4, "SIGILL"))
+            Some((SIGILL, "SIGILL"))
         } else if self.contains(Self::SIGABRT) {
-            Some((-6, "SIGABRT"))
+            Some((SIGABRT, "SIGABRT"))
         } else if self.contains(Self::SIGFPE) {
-            Some((-8, "SIGFPE"))
+            Some((SIGFPE, "SIGFPE"))
         } else if self.contains(Self::SIGKILL) {
-            Some((-9, "SIGKILL"))
+            Some((SIGKILL, "SIGKILL"))
         } else if self.contains(Self::SIGSEGV) {
-            Some((-11, "SIGSEGV"))
+            Some((SIGSEGV, "SIGSEGV"))
         } else {
             None
         }
@@ -115,8 +133,34 @@ impl SignalFlags {
 #[repr(C, align(16))]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SignalAction {
+    // 处理函数的地址, 或者SIG_DFL, SIG_IGN
     pub handler: usize,
     pub mask: SignalFlags,
+    pub flags: SigActionFlags,
 }
 
 pub type SignalActions = [SignalAction; MAX_SIG + 1];
+
+// 传给信号处理函数的第二个参数
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SigInfo {
+    pub signo: i32,
+    pub errno: i32,
+    pub code: i32,
+    // SIGSEGV时是出错的地址, 其他信号为0
+    pub addr: usize,
+}
+
+// 调用信号处理函数前压到用户栈上, sigreturn时从这里恢复
+// 只保存用户态能修改的寄存器, trap上下文中内核的部分不暴露给用户态
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SignalFrame {
+    pub info: SigInfo,
+    // 被打断时的通用寄存器和pc
+    pub x: [usize; 32],
+    pub sepc: usize,
+    // 进入处理函数之前的信号掩码
+    pub mask: SignalFlags,
+}
@@ -0,0 +1,102 @@
+use alloc::boxed::Box;
+
+use crate::{
+    constant::{THREAD_STACK_TOP, TRAP_CONTEXT_VPN, USER_STACK_SIZE_BY_PAGE},
+    mm::{
+        address::{PhysPageNum, VirtAddr, VirtPageNum, VirtPageSpan},
+        kernel_stack::KernelStack,
+    },
+    process::context::Context as TaskContext,
+    trap::context::Context as TrapContext,
+};
+
+use super::pcb::ProcessControlBlock;
+use super::queue::SchedInfo;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Ready,
+    Running,
+    //在某个等待队列上睡眠, 或者在sigsuspend中等待信号
+    Blocked,
+    Zombie,
+}
+
+// 线程tid的trap上下文所在的虚拟页, 从TRAP_CONTEXT_VPN开始向下排布
+pub fn trap_ctx_vpn(tid: usize) -> VirtPageNum {
+    TRAP_CONTEXT_VPN - tid
+}
+
+// thread_create创建的线程tid的用户栈
+pub fn user_stack_span(tid: usize) -> VirtPageSpan {
+    let top = VirtAddr(THREAD_STACK_TOP).floor() - tid * (USER_STACK_SIZE_BY_PAGE + 1);
+    (top - USER_STACK_SIZE_BY_PAGE..top).into()
+}
+
+pub struct ThreadControlBlock {
+    // 进程内的线程号, 在整个生命周期中不会改变, 除非调用了exec
+    pub tid: usize,
+    // 所属的进程, 进程在最后一个线程退出时才退出
+    pub process: *mut ProcessControlBlock,
+    // 内核栈的代理对象, 在整个生命周期中, 该对象代理的内核栈不会改变
+    pub kernel_stack: KernelStack,
+    //task上下文
+    pub task_context: TaskContext,
+    //线程状态
+    pub state: State,
+    //trap上下文的物理页号
+    pub trap_ctx_ppn: PhysPageNum,
+    //thread_create创建的线程的用户栈, 主线程用的是加载elf时分配的用户栈
+    pub user_stack: Option<VirtPageSpan>,
+    pub exit_code: i32,
+    //调度相关的信息
+    pub sched: SchedInfo,
+}
+
+unsafe impl Send for ThreadControlBlock {}
+
+impl ThreadControlBlock {
+    // trap上下文页由调用者分配和初始化
+    pub fn new(
+        process: *mut ProcessControlBlock,
+        tid: usize,
+        trap_ctx_ppn: PhysPageNum,
+        user_stack: Option<VirtPageSpan>,
+        sched: SchedInfo,
+    ) -> Box<Self> {
+        let kernel_stack = KernelStack::new();
+        let kernel_stack_btm = kernel_stack.btm().0;
+        Box::new(Self {
+            tid,
+            process,
+            kernel_stack,
+            task_context: TaskContext::goto_trap_return(kernel_stack_btm),
+            state: State::Ready,
+            trap_ctx_ppn,
+            user_stack,
+            exit_code: 0,
+            sched,
+        })
+    }
+
+    pub fn task_ctx(&mut self) -> *mut TaskContext {
+        &mut self.task_context as *mut _
+    }
+
+    pub fn trap_ctx(&self) -> &'static mut TrapContext {
+        self.trap_ctx_ppn.read_as()
+    }
+
+    // 用户空间中trap上下文的地址
+    pub fn trap_ctx_va(&self) -> VirtAddr {
+        trap_ctx_vpn(self.tid).floor()
+    }
+
+    pub fn process(&self) -> &'static mut ProcessControlBlock {
+        unsafe { &mut *self.process }
+    }
+
+    pub fn is_zombie(&self) -> bool {
+        self.state == State::Zombie
+    }
+}
@@ -0,0 +1,60 @@
+use alloc::collections::VecDeque;
+
+use super::{
+    processor::PROCESSOR,
+    queue::QUEUE,
+    tcb::{State, ThreadControlBlock},
+};
+
+// 等待队列, 队列中的线程处于阻塞状态, 不会出现在就绪队列中
+pub struct WaitQueue {
+    queue: VecDeque<*mut ThreadControlBlock>,
+}
+
+impl WaitQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+
+    // 阻塞当前线程直到被唤醒
+    // 线程也可能因为进程收到信号或者正在退出而被唤醒, 调用者需要自己检查等待的条件是否满足
+    pub fn wait(&mut self) {
+        let processor = PROCESSOR.exclusive_access();
+        let task = processor.current_thread().unwrap() as *mut ThreadControlBlock;
+        self.queue.push_back(task);
+        processor.block_current().schedule();
+        //被信号唤醒时自己还留在队列里
+        self.queue.retain(|&t| t != task);
+    }
+
+    pub fn wake_one(&mut self) -> bool {
+        match self.queue.pop_front() {
+            Some(task) => {
+                wakeup(task);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn wake_all(&mut self) {
+        while self.wake_one() {}
+    }
+}
+
+impl Default for WaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 把阻塞的线程放回就绪队列, 对不处于阻塞状态的线程没有影响
+pub fn wakeup(task: *mut ThreadControlBlock) {
+    let tcb = unsafe { &mut *task };
+    if tcb.state == State::Blocked {
+        tcb.state = State::Ready;
+        QUEUE.lock().push(task);
+    }
+}
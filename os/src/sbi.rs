@@ -24,6 +24,28 @@ pub fn shutdown(failure: bool) -> ! {
     unreachable!()
 }
 
+/// use sbi call to reboot the machine, `warm` selects a warm reboot instead of a cold one
+pub fn reboot(warm: bool) -> ! {
+    use sbi_rt::{system_reset, ColdReboot, NoReason, WarmReboot};
+    if warm {
+        system_reset(WarmReboot, NoReason);
+    } else {
+        system_reset(ColdReboot, NoReason);
+    }
+    unreachable!()
+}
+
+/// use sbi call to start hart `hartid` at `start_addr` with `opaque` in a1, return whether it succeeded
+pub fn hart_start(hartid: usize, start_addr: usize, opaque: usize) -> bool {
+    sbi_rt::hart_start(hartid, start_addr, opaque).error == 0
+}
+
+/// use sbi call to flush the TLB of all harts, including the current one
+pub fn remote_sfence_vma_all() {
+    // a hart_mask_base of usize::MAX selects all harts, a size of usize::MAX flushes everything
+    sbi_rt::remote_sfence_vma(0, usize::MAX, 0, usize::MAX);
+}
+
 /// use sbi call to set timer
 pub fn set_timer(timer: usize) {
     sbi_rt::set_timer(timer as _);
@@ -0,0 +1,31 @@
+use crate::process::{processor::PROCESSOR, wait_queue::WaitQueue};
+
+// 条件变量, 醒来后调用者需要重新检查等待的条件
+pub struct Condvar {
+    wait_queue: WaitQueue,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self {
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    pub fn signal(&mut self) {
+        self.wait_queue.wake_one();
+    }
+
+    // 调用者负责在睡眠前释放互斥锁, 醒来后重新获取
+    // 进程正在退出或者有待处理的信号时不睡眠
+    pub fn wait(&mut self) {
+        if !PROCESSOR
+            .exclusive_access()
+            .current()
+            .unwrap()
+            .interrupted()
+        {
+            self.wait_queue.wait();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+
+// 死锁检测关心的资源, 互斥锁和信号量都按下标区分
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Resource {
+    Mutex(usize),
+    Semaphore(usize),
+}
+
+// 银行家算法: 线程申请资源时先假设它申请成功,
+// 如果此时找不到一个让所有线程都能运行完的顺序, 就拒绝这次申请
+#[derive(Default)]
+pub struct DeadlockDetector {
+    pub enabled: bool,
+    available: BTreeMap<Resource, usize>,
+    // (tid, 资源) -> 数量
+    allocation: BTreeMap<(usize, Resource), usize>,
+    need: BTreeMap<(usize, Resource), usize>,
+}
+
+fn inc(map: &mut BTreeMap<(usize, Resource), usize>, key: (usize, Resource)) {
+    *map.entry(key).or_insert(0) += 1;
+}
+
+fn dec(map: &mut BTreeMap<(usize, Resource), usize>, key: (usize, Resource)) {
+    if let Some(count) = map.get_mut(&key) {
+        *count -= 1;
+        if *count == 0 {
+            map.remove(&key);
+        }
+    }
+}
+
+impl DeadlockDetector {
+    pub fn add_resource(&mut self, res: Resource, count: usize) {
+        self.available.insert(res, count);
+    }
+
+    // 线程tid申请一个res, 开启检测且会导致不安全状态时返回false, 不记录这次申请
+    pub fn request(&mut self, tid: usize, res: Resource) -> bool {
+        inc(&mut self.need, (tid, res));
+        if self.enabled && !self.is_safe() {
+            dec(&mut self.need, (tid, res));
+            return false;
+        }
+        true
+    }
+
+    // 申请被放弃, 比如等待时被信号打断
+    pub fn cancel(&mut self, tid: usize, res: Resource) {
+        dec(&mut self.need, (tid, res));
+    }
+
+    // 申请的资源已经拿到
+    pub fn acquire(&mut self, tid: usize, res: Resource) {
+        dec(&mut self.need, (tid, res));
+        inc(&mut self.allocation, (tid, res));
+        if let Some(available) = self.available.get_mut(&res) {
+            *available = available.saturating_sub(1);
+        }
+    }
+
+    // 信号量可以由没有down过的线程up, 此时只增加可用的数量
+    pub fn release(&mut self, tid: usize, res: Resource) {
+        dec(&mut self.allocation, (tid, res));
+        *self.available.entry(res).or_insert(0) += 1;
+    }
+
+    fn is_safe(&self) -> bool {
+        let mut work = self.available.clone();
+        let mut unfinished: BTreeSet<usize> = self
+            .allocation
+            .keys()
+            .chain(self.need.keys())
+            .map(|&(tid, _)| tid)
+            .collect();
+        loop {
+            let runnable = unfinished.iter().copied().find(|&tid| {
+                self.need
+                    .iter()
+                    .filter(|((t, _), _)| *t == tid)
+                    .all(|((_, res), &count)| work.get(res).copied().unwrap_or(0) >= count)
+            });
+            match runnable {
+                Some(tid) => {
+                    //线程运行完后归还它持有的所有资源
+                    for ((_, res), &count) in self.allocation.iter().filter(|((t, _), _)| *t == tid)
+                    {
+                        *work.entry(*res).or_insert(0) += count;
+                    }
+                    unfinished.remove(&tid);
+                }
+                None => return unfinished.is_empty(),
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use core::mem::size_of;
+
+use alloc::collections::{BTreeMap, VecDeque};
+
+use crate::{
+    mm::{
+        address::{PhysAddr, VirtAddr},
+        page_table::TopLevelEntry,
+        uaccess::{check_user_range, user_page},
+    },
+    process::{processor::PROCESSOR, tcb::ThreadControlBlock, wait_queue::wakeup},
+    syscall::{Errno, SyscallResult},
+    timer::{add_timer, get_time, remove_timer},
+};
+
+use super::spin::SpinLock;
+
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+// 所有futex都按物理地址区分, 私有与否没有区别
+pub const FUTEX_PRIVATE_FLAG: usize = 128;
+
+// 物理地址 -> 阻塞在该地址上的线程, 共享同一物理页的进程之间也能互相唤醒
+lazy_static! {
+    static ref FUTEXES: SpinLock<BTreeMap<PhysAddr, VecDeque<*mut ThreadControlBlock>>> =
+        SpinLock::new(BTreeMap::new());
+}
+
+// 写时复制的页写入后会换成别的物理页, 所以先处理写时复制, 保证同一进程中的key不变
+fn futex_key(page_table: TopLevelEntry, uaddr: usize) -> SyscallResult<PhysAddr> {
+    check_user_range(uaddr, size_of::<u32>())?;
+    let (vpn, offset) = VirtAddr(uaddr).split();
+    Ok(user_page(page_table, vpn, true)?.phys_addr(offset))
+}
+
+// 把task从key的等待队列中移除, 返回它是否还在队列中
+fn dequeue(key: PhysAddr, task: *mut ThreadControlBlock) -> bool {
+    let mut futexes = FUTEXES.lock();
+    let queue = match futexes.get_mut(&key) {
+        Some(queue) => queue,
+        None => return false,
+    };
+    match queue.iter().position(|&t| t == task) {
+        Some(idx) => {
+            queue.remove(idx);
+            if queue.is_empty() {
+                futexes.remove(&key);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+// uaddr处的值等于val时阻塞, 直到被futex_wake唤醒或者在expire时刻超时
+pub fn futex_wait(uaddr: usize, val: u32, expire: Option<usize>) -> SyscallResult {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = thread.process();
+    let key = futex_key(task.page_table(), uaddr)?;
+    //持有大内核锁, 检查和入队之间不会有其他线程调用futex_wake
+    if *key.as_ref::<u32>() != val {
+        return Err(Errno::EAGAIN);
+    }
+    if task.interrupted() {
+        return Err(Errno::EINTR);
+    }
+    let current = thread as *mut ThreadControlBlock;
+    FUTEXES.lock().entry(key).or_default().push_back(current);
+    if let Some(expire) = expire {
+        add_timer(expire, current);
+    }
+    PROCESSOR.exclusive_access().block_current().schedule();
+    if expire.is_some() {
+        remove_timer(current);
+    }
+    //被futex_wake唤醒时已经不在队列中了
+    if !dequeue(key, current) {
+        return Ok(0);
+    }
+    match expire {
+        Some(expire) if get_time() >= expire => Err(Errno::ETIMEDOUT),
+        _ => Err(Errno::EINTR),
+    }
+}
+
+// 唤醒最多count个阻塞在uaddr上的线程, 返回唤醒的个数
+pub fn futex_wake(uaddr: usize, count: usize) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let key = futex_key(task.page_table(), uaddr)?;
+    let mut futexes = FUTEXES.lock();
+    let queue = match futexes.get_mut(&key) {
+        Some(queue) => queue,
+        None => return Ok(0),
+    };
+    let mut woken = 0;
+    while woken < count {
+        match queue.pop_front() {
+            Some(task) => {
+                wakeup(task);
+                woken += 1;
+            }
+            None => break,
+        }
+    }
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    Ok(woken)
+}
@@ -1 +1,7 @@
-pub mod up;
+pub mod condvar;
+pub mod deadlock;
+pub mod futex;
+pub mod mutex;
+pub mod per_hart;
+pub mod semaphore;
+pub mod spin;
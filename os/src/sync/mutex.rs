@@ -0,0 +1,96 @@
+use crate::{
+    process::{processor::PROCESSOR, wait_queue::WaitQueue},
+    syscall::{Errno, SyscallResult},
+};
+
+// 供用户线程使用的互斥锁, 由进程持有, 通过下标引用
+// 进程正在退出或者有待处理的信号时, lock返回EINTR, 不是持有者的线程unlock返回EPERM
+pub trait Mutex: Send {
+    fn lock(&mut self) -> SyscallResult<()>;
+    fn unlock(&mut self) -> SyscallResult<()>;
+}
+
+fn current_tid() -> usize {
+    PROCESSOR.exclusive_access().current_thread().unwrap().tid
+}
+
+// 拿不到锁时让出cpu, 之后再重试
+pub struct SpinMutex {
+    //持有锁的线程的tid
+    owner: Option<usize>,
+}
+
+impl SpinMutex {
+    pub fn new() -> Self {
+        Self { owner: None }
+    }
+}
+
+impl Mutex for SpinMutex {
+    fn lock(&mut self) -> SyscallResult<()> {
+        while self.owner.is_some() {
+            if PROCESSOR
+                .exclusive_access()
+                .current()
+                .unwrap()
+                .interrupted()
+            {
+                return Err(Errno::EINTR);
+            }
+            PROCESSOR.exclusive_access().suspend_current().schedule();
+        }
+        self.owner = Some(current_tid());
+        Ok(())
+    }
+
+    fn unlock(&mut self) -> SyscallResult<()> {
+        if self.owner != Some(current_tid()) {
+            return Err(Errno::EPERM);
+        }
+        self.owner = None;
+        Ok(())
+    }
+}
+
+// 拿不到锁时阻塞在等待队列上, 解锁时唤醒一个等待的线程
+pub struct BlockingMutex {
+    //持有锁的线程的tid
+    owner: Option<usize>,
+    wait_queue: WaitQueue,
+}
+
+impl BlockingMutex {
+    pub fn new() -> Self {
+        Self {
+            owner: None,
+            wait_queue: WaitQueue::new(),
+        }
+    }
+}
+
+impl Mutex for BlockingMutex {
+    fn lock(&mut self) -> SyscallResult<()> {
+        while self.owner.is_some() {
+            if PROCESSOR
+                .exclusive_access()
+                .current()
+                .unwrap()
+                .interrupted()
+            {
+                return Err(Errno::EINTR);
+            }
+            self.wait_queue.wait();
+        }
+        self.owner = Some(current_tid());
+        Ok(())
+    }
+
+    fn unlock(&mut self) -> SyscallResult<()> {
+        if self.owner != Some(current_tid()) {
+            return Err(Errno::EPERM);
+        }
+        self.owner = None;
+        self.wait_queue.wake_one();
+        Ok(())
+    }
+}
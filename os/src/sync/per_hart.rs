@@ -0,0 +1,30 @@
+//! Per-hart data
+
+use core::cell::UnsafeCell;
+
+use crate::{constant::MAX_HARTS, hart::hart_id};
+
+/// One instance of `T` for every hart. A hart only ever touches its own
+/// instance, so no locking is needed.
+pub struct PerHart<T> {
+    inner: [UnsafeCell<T>; MAX_HARTS],
+}
+
+unsafe impl<T> Sync for PerHart<T> {}
+unsafe impl<T> Send for PerHart<T> {}
+
+impl<T> PerHart<T> {
+    pub fn new(init: impl Fn() -> T) -> Self {
+        Self {
+            inner: core::array::from_fn(|_| UnsafeCell::new(init())),
+        }
+    }
+
+    /// Access the instance of the current hart.
+    ///
+    /// A task may resume on another hart after `schedule`, so the reference
+    /// must not be kept across it.
+    pub fn exclusive_access(&self) -> &mut T {
+        unsafe { &mut *self.inner[hart_id()].get() }
+    }
+}
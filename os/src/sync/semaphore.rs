@@ -0,0 +1,41 @@
+use crate::{
+    process::{processor::PROCESSOR, wait_queue::WaitQueue},
+    syscall::{Errno, SyscallResult},
+};
+
+// 计数信号量, 计数为0时down会阻塞
+pub struct Semaphore {
+    count: usize,
+    wait_queue: WaitQueue,
+}
+
+impl Semaphore {
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.count += 1;
+        self.wait_queue.wake_one();
+    }
+
+    // 进程正在退出或者有待处理的信号时返回EINTR
+    pub fn down(&mut self) -> SyscallResult<()> {
+        while self.count == 0 {
+            if PROCESSOR
+                .exclusive_access()
+                .current()
+                .unwrap()
+                .interrupted()
+            {
+                return Err(Errno::EINTR);
+            }
+            self.wait_queue.wait();
+        }
+        self.count -= 1;
+        Ok(())
+    }
+}
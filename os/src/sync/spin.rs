@@ -0,0 +1,141 @@
+//! Spin locks shared by all harts
+
+use core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use riscv::register::sstatus;
+
+use crate::{constant::MAX_HARTS, hart::hart_id};
+
+/// A spin lock which disables interrupts of the current hart while it is held,
+/// so an interrupt handler on the same hart never spins on it forever.
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+// 内核中到处都是裸指针, 这里不要求T: Send
+unsafe impl<T> Sync for SpinLock<T> {}
+unsafe impl<T> Send for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Lock it and get the guard, the guard must not be held across `schedule`.
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        push_off();
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        SpinLockGuard { lock: self }
+    }
+}
+
+pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+        pop_off();
+    }
+}
+
+#[derive(Clone, Copy)]
+struct IntrState {
+    // 当前hart持有的锁的数量
+    depth: usize,
+    // 拿第一把锁之前是否开着中断
+    enabled: bool,
+}
+
+// 每个hart只访问自己的一项, 且访问时已经关了中断
+static mut INTR_STATES: [IntrState; MAX_HARTS] = [IntrState {
+    depth: 0,
+    enabled: false,
+}; MAX_HARTS];
+
+fn push_off() {
+    let enabled = sstatus::read().sie();
+    unsafe {
+        sstatus::clear_sie();
+        let state = &mut INTR_STATES[hart_id()];
+        if state.depth == 0 {
+            state.enabled = enabled;
+        }
+        state.depth += 1;
+    }
+}
+
+fn pop_off() {
+    unsafe {
+        let state = &mut INTR_STATES[hart_id()];
+        state.depth -= 1;
+        if state.depth == 0 && state.enabled {
+            sstatus::set_sie();
+        }
+    }
+}
+
+/// The big kernel lock.
+///
+/// Processes reference each other through raw pointers (parents, children,
+/// wait queues, signals), so only one hart may run kernel code at a time.
+/// It is taken on every trap from user space and released right before
+/// returning to user space; idle harts give it up while polling for work.
+/// Interrupts are never enabled in S-mode, so it doesn't need to touch `sie`.
+pub struct KernelLock {
+    locked: AtomicBool,
+}
+
+impl KernelLock {
+    const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    pub fn acquire(&self) {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+    }
+
+    pub fn release(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
+
+pub static KERNEL_LOCK: KernelLock = KernelLock::new();
@@ -1,84 +1,78 @@
-
-
 use crate::{
+    constant::PATH_MAX,
     fs::{
         inode::{OSInode, OpenFlags},
         pipe::make_pipe,
         SeekType,
     },
-    mm::address::{UserBuffer, VirtAddr},
+    mm::{
+        address::UserBuffer,
+        uaccess::{copy_str_from_user, copy_to_user},
+    },
     process::processor::PROCESSOR,
     types::CStr,
 };
 
-pub fn sys_dup(fd: usize) -> isize {
+use super::{Errno, SyscallResult};
+
+pub fn sys_dup(fd: usize) -> SyscallResult {
     let task = PROCESSOR.exclusive_access().current().unwrap();
-    match task.fd_at(fd) {
-        Some(file) => task.add_fd(file) as isize,
-        None => -1,
-    }
+    let file = task.fd_at(fd).ok_or(Errno::EBADF)?;
+    Ok(task.add_fd(file))
 }
 
-pub fn sys_write(fd: usize, buf: usize, len: usize) -> isize {
+pub fn sys_write(fd: usize, buf: usize, len: usize) -> SyscallResult {
     let task = PROCESSOR.exclusive_access().current().unwrap();
     let page_table = task.page_table();
-    match task.fd_at(fd) {
-        Some(file) => {
-            let user_buf = UserBuffer::new(VirtAddr(buf)..VirtAddr(buf + len), page_table);
-            file.write(user_buf)
-        }
-        None => -1,
-    }
+    let file = task.fd_at(fd).ok_or(Errno::EBADF)?;
+    let user_buf = UserBuffer::new(buf, len, page_table, false)?;
+    file.write(user_buf)
 }
 
-pub fn sys_read(fd: usize, buf: usize, len: usize) -> isize {
+pub fn sys_read(fd: usize, buf: usize, len: usize) -> SyscallResult {
     let task = PROCESSOR.exclusive_access().current().unwrap();
     let page_table = task.page_table();
-    match task.fd_at(fd) {
-        Some(file) => {
-            let user_buf = UserBuffer::new(VirtAddr(buf)..VirtAddr(buf + len), page_table);
-            file.read(user_buf)
-        }
-        None => -1,
-    }
+    let file = task.fd_at(fd).ok_or(Errno::EBADF)?;
+    let user_buf = UserBuffer::new(buf, len, page_table, true)?;
+    file.read(user_buf)
 }
 
-pub fn sys_seek(fd: usize, offset: isize, whence: usize) -> isize {
+pub fn sys_seek(fd: usize, offset: isize, whence: usize) -> SyscallResult {
     let seek_ty = match whence {
         0 => SeekType::Set,
         1 => SeekType::Cur,
         2 => SeekType::End,
-        _ => return -1,
+        _ => return Err(Errno::EINVAL),
     };
     let task = PROCESSOR.exclusive_access().current().unwrap();
-    match task.fd_at(fd) {
-        Some(file) => file.seek(seek_ty, offset as i32),
-        None => -1,
-    }
+    let file = task.fd_at(fd).ok_or(Errno::EBADF)?;
+    file.seek(seek_ty, offset as i32)
 }
 
-pub fn sys_open(path: CStr, flags: usize) -> isize {
+pub fn sys_open(path: CStr, flags: usize) -> SyscallResult {
     let pcb = PROCESSOR.exclusive_access().current().unwrap();
-    let path = pcb.page_table().translate_virt_str(path);
-    if let Some(inode) = OSInode::open(&path, OpenFlags::from_bits(flags as u32).unwrap()) {
-        pcb.add_fd(inode) as isize
-    } else {
-        -1
-    }
+    let path = copy_str_from_user(pcb.page_table(), path, PATH_MAX)?;
+    let flags = OpenFlags::from_bits(flags as u32).ok_or(Errno::EINVAL)?;
+    let inode = OSInode::open(&path, flags)?;
+    Ok(pcb.add_fd(inode))
 }
 
-pub fn sys_close(fd: usize) -> isize {
+pub fn sys_close(fd: usize) -> SyscallResult {
     let pcb = PROCESSOR.exclusive_access().current().unwrap();
     pcb.close_fd(fd)
 }
 
-pub fn sys_pipe(pipe: *mut usize) -> isize {
+pub fn sys_pipe(pipe: *mut usize) -> SyscallResult {
     let pcb = PROCESSOR.exclusive_access().current().unwrap();
     let page_table = pcb.page_table();
     let (reader, writer) = make_pipe();
     let read_fd = pcb.add_fd(reader);
     let write_fd = pcb.add_fd(writer);
-    *page_table.translate_virt_mut(pipe) = read_fd;
-    *page_table.translate_virt_mut(unsafe { pipe.add(1) }) = write_fd;
-    0
+    //写不回去时用户拿不到这两个fd, 直接关掉
+    if let Err(errno) = copy_to_user(page_table, pipe as *mut [usize; 2], [read_fd, write_fd]) {
+        pcb.close_fd(read_fd)?;
+        pcb.close_fd(write_fd)?;
+        return Err(errno);
+    }
+    Ok(0)
 }
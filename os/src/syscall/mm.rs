@@ -0,0 +1,149 @@
+use crate::{
+    constant::{MMAP_BASE, MMAP_TOP, PAGE_MASK},
+    mm::{
+        address::{VirtAddr, VirtPageSpan},
+        virt_mem_area::{FileMapping, MapType, Permission},
+    },
+    process::processor::PROCESSOR,
+};
+
+use super::{Errno, SyscallResult};
+
+bitflags! {
+    #[derive(Clone, Copy, Debug)]
+    pub struct ProtFlags: usize {
+        const READ = 1 << 0;
+        const WRITE = 1 << 1;
+        const EXEC = 1 << 2;
+    }
+}
+
+bitflags! {
+    #[derive(Clone, Copy, Debug)]
+    pub struct MmapFlags: usize {
+        const SHARED = 1 << 0;
+        const PRIVATE = 1 << 1;
+        const FIXED = 1 << 4;
+        const ANONYMOUS = 1 << 5;
+    }
+}
+
+impl From<ProtFlags> for Permission {
+    fn from(prot: ProtFlags) -> Self {
+        let mut perm = Permission::U;
+        //risc-v的页表项不允许只写不读
+        if prot.intersects(ProtFlags::READ | ProtFlags::WRITE) {
+            perm |= Permission::R;
+        }
+        if prot.contains(ProtFlags::WRITE) {
+            perm |= Permission::W;
+        }
+        if prot.contains(ProtFlags::EXEC) {
+            perm |= Permission::X;
+        }
+        perm
+    }
+}
+
+//和ELF加载器一样, 默认不允许同时可写可执行
+fn check_wx(prot: ProtFlags) -> SyscallResult<()> {
+    if !cfg!(feature = "allow_wx") && prot.contains(ProtFlags::WRITE | ProtFlags::EXEC) {
+        Err(Errno::EACCES)
+    } else {
+        Ok(())
+    }
+}
+
+//检查[addr, addr + len)是否落在mmap区域中
+fn mmap_range(addr: usize, len: usize) -> SyscallResult<VirtPageSpan> {
+    if addr & PAGE_MASK != 0 || len == 0 {
+        return Err(Errno::EINVAL);
+    }
+    let end = addr.checked_add(len).ok_or(Errno::EINVAL)?;
+    if addr < MMAP_BASE || end > MMAP_TOP {
+        return Err(Errno::EINVAL);
+    }
+    Ok(VirtPageSpan::new(
+        VirtAddr(addr).floor()..VirtAddr(end).ceil(),
+    ))
+}
+
+// 不带MAP_FIXED时忽略addr, 由内核选择映射的位置
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> SyscallResult {
+    let prot = ProtFlags::from_bits(prot).ok_or(Errno::EINVAL)?;
+    let flags = MmapFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+    let shared = flags.contains(MmapFlags::SHARED);
+    if shared == flags.contains(MmapFlags::PRIVATE) || len == 0 || offset & PAGE_MASK != 0 {
+        return Err(Errno::EINVAL);
+    }
+    check_wx(prot)?;
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let map_type = if flags.contains(MmapFlags::ANONYMOUS) {
+        if shared {
+            MapType::Shared
+        } else {
+            MapType::Lazy
+        }
+    } else {
+        let file = task.fd_at(fd).ok_or(Errno::EBADF)?;
+        let inode = file.inode().ok_or(Errno::ENODEV)?;
+        if !file.readable() || (shared && prot.contains(ProtFlags::WRITE) && !file.writable()) {
+            return Err(Errno::EACCES);
+        }
+        //inode的偏移是u32, 映射的范围不能超出文件能表示的范围
+        match offset.checked_add(len) {
+            Some(end) if end <= u32::MAX as usize + 1 => {}
+            _ => return Err(Errno::EOVERFLOW),
+        }
+        MapType::File(FileMapping {
+            inode,
+            offset,
+            shared,
+            writable: file.writable(),
+        })
+    };
+    let range = if flags.contains(MmapFlags::FIXED) {
+        mmap_range(addr, len)?
+    } else {
+        let pages = VirtAddr(len).ceil().0;
+        let start = task.mem_set.find_free_area(pages).ok_or(Errno::ENOMEM)?;
+        VirtPageSpan::new(start..start + pages)
+    };
+    task.mem_set.mmap(range, map_type, prot.into());
+    Ok(range.start.floor().0)
+}
+
+// 只能修改已经映射的区域, PROT_NONE的页保留内容, 访问时触发SIGSEGV
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> SyscallResult {
+    let prot = ProtFlags::from_bits(prot).ok_or(Errno::EINVAL)?;
+    if addr & PAGE_MASK != 0 {
+        return Err(Errno::EINVAL);
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+    let end = addr.checked_add(len).ok_or(Errno::ENOMEM)?;
+    check_wx(prot)?;
+    let range = VirtPageSpan::new(VirtAddr(addr).floor()..VirtAddr(end).ceil());
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    task.mem_set.mprotect(range, prot.into())?;
+    Ok(0)
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> SyscallResult {
+    let range = mmap_range(addr, len)?;
+    PROCESSOR
+        .exclusive_access()
+        .current()
+        .unwrap()
+        .mem_set
+        .munmap(range);
+    Ok(0)
+}
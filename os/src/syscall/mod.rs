@@ -1,15 +1,33 @@
+pub mod errno;
 mod fs;
+mod mm;
 mod process;
 pub mod signal;
+mod sync;
+mod thread;
 
-pub use errorno::*;
+pub use errno::{Errno, SyscallResult};
 use fs::*;
+use mm::*;
 use process::*;
+use sync::*;
+use thread::*;
 
 use crate::{
-    syscall::signal::{sys_kill, sys_sigaction, sys_sigprocmask, sys_sigret},
+    process::{
+        itimer::ITimerVal,
+        processor::PROCESSOR,
+        rlimit::RLimit,
+        rusage::{RUsage, Tms},
+        signal::{SignalFlags, SIGSYS},
+    },
+    syscall::signal::{
+        sys_kill, sys_sigaction, sys_sigpending, sys_sigprocmask, sys_sigret, sys_sigsuspend,
+    },
+    timer::TimeSpec,
     types::CStr,
 };
+use log::warn;
 
 pub mod syscall_id {
     pub const DUP: usize = 24;
@@ -20,32 +38,55 @@ pub mod syscall_id {
     pub const READ: usize = 63;
     pub const WRITE: usize = 64;
     pub const EXIT: usize = 93;
+    pub const EXIT_GROUP: usize = 94;
+    pub const FUTEX: usize = 98;
+    pub const NANOSLEEP: usize = 101;
+    pub const GETITIMER: usize = 102;
+    pub const SETITIMER: usize = 103;
     pub const YIELD: usize = 124;
     pub const KILL: usize = 129;
+    pub const SIGSUSPEND: usize = 133;
     pub const SIGACTION: usize = 134;
     pub const SIGPROCMASK: usize = 135;
+    pub const SIGPENDING: usize = 136;
     pub const SIGRET: usize = 139;
+    pub const SETPRIORITY: usize = 140;
+    pub const GETPRIORITY: usize = 141;
+    pub const REBOOT: usize = 142;
+    pub const TIMES: usize = 153;
+    pub const GETRLIMIT: usize = 163;
+    pub const SETRLIMIT: usize = 164;
+    pub const GETRUSAGE: usize = 165;
     pub const GET_TIME: usize = 169;
     pub const GETPID: usize = 172;
     pub const SBRK: usize = 214;
+    pub const MUNMAP: usize = 215;
     pub const FORK: usize = 220;
     pub const EXEC: usize = 221;
+    pub const MMAP: usize = 222;
+    pub const MPROTECT: usize = 226;
     pub const WAITPID: usize = 260;
+    pub const ENABLE_DEADLOCK_DETECT: usize = 469;
+    pub const THREAD_CREATE: usize = 1000;
+    pub const GETTID: usize = 1001;
+    pub const WAITTID: usize = 1002;
+    pub const MUTEX_CREATE: usize = 1010;
+    pub const MUTEX_LOCK: usize = 1011;
+    pub const MUTEX_UNLOCK: usize = 1012;
+    pub const SEMAPHORE_CREATE: usize = 1020;
+    pub const SEMAPHORE_UP: usize = 1021;
+    pub const SEMAPHORE_DOWN: usize = 1022;
+    pub const CONDVAR_CREATE: usize = 1030;
+    pub const CONDVAR_SIGNAL: usize = 1031;
+    pub const CONDVAR_WAIT: usize = 1032;
+    // 非标准的系统调用, linux中关机是reboot的一个命令
+    pub const SHUTDOWN: usize = 2000;
 }
 
-#[allow(unused)]
-pub mod errorno {
-    pub const EOF: isize = 0;
-    pub const UNREADABLE: isize = -2;
-    pub const UNWRITABLE: isize = -3;
-    pub const UNSEEKABLE: isize = -4;
-    pub const SEEK_OUT_OF_RANGE: isize = -5;
-    pub const PIPE_READER_CLOSED: isize = -6;
-}
-
-pub fn syscall(id: usize, [arg0, arg1, arg2]: [usize; 3]) -> isize {
+// 参数依次来自a0~a5
+pub fn syscall(id: usize, [arg0, arg1, arg2, arg3, arg4, arg5]: [usize; 6]) -> isize {
     use syscall_id::*;
-    match id {
+    let ret = match id {
         DUP => sys_dup(arg0),
         OPEN => sys_open(arg0 as CStr, arg1),
         CLOSE => sys_close(arg0),
@@ -54,17 +95,66 @@ pub fn syscall(id: usize, [arg0, arg1, arg2]: [usize; 3]) -> isize {
         READ => sys_read(arg0, arg1, arg2),
         WRITE => sys_write(arg0, arg1, arg2),
         EXIT => sys_exit(arg0 as i32),
+        EXIT_GROUP => sys_exit_group(arg0 as i32),
+        FUTEX => sys_futex(arg0, arg1, arg2 as u32, arg3 as *const TimeSpec),
+        NANOSLEEP => sys_nanosleep(arg0 as *const TimeSpec, arg1 as *mut TimeSpec),
+        GETITIMER => sys_getitimer(arg0, arg1 as *mut ITimerVal),
+        SETITIMER => sys_setitimer(arg0, arg1 as *const ITimerVal, arg2 as *mut ITimerVal),
         YIELD => sys_yield(),
         KILL => sys_kill(arg0, arg1),
         SIGACTION => sys_sigaction(arg0, arg1, arg2),
-        SIGPROCMASK => sys_sigprocmask(arg0),
+        SIGSUSPEND => sys_sigsuspend(arg0 as *const u64),
+        SIGPROCMASK => sys_sigprocmask(arg0, arg1 as *const u64, arg2 as *mut u64),
+        SIGPENDING => sys_sigpending(arg0 as *mut u64),
         SIGRET => sys_sigret(),
         GET_TIME => sys_get_time(),
+        GETRLIMIT => sys_getrlimit(arg0, arg1 as *mut RLimit),
+        SETRLIMIT => sys_setrlimit(arg0, arg1 as *const RLimit),
+        TIMES => sys_times(arg0 as *mut Tms),
+        GETRUSAGE => sys_getrusage(arg0 as isize, arg1 as *mut RUsage),
         SBRK => sys_sbrk(arg0 as isize),
         GETPID => sys_getpid(),
-        WAITPID => sys_wait(arg0 as isize, arg1 as *mut i32),
+        WAITPID => sys_wait(arg0 as isize, arg1 as *mut i32, arg2),
         FORK => sys_fork(),
-        EXEC => sys_exec(arg0 as CStr, arg1 as *const CStr),
-        _ => panic!("unsupported syscall id {}", id),
+        EXEC => sys_exec(arg0 as CStr, arg1 as *const CStr, arg2 as *const CStr),
+        MMAP => sys_mmap(arg0, arg1, arg2, arg3, arg4, arg5),
+        MUNMAP => sys_munmap(arg0, arg1),
+        MPROTECT => sys_mprotect(arg0, arg1, arg2),
+        THREAD_CREATE => sys_thread_create(arg0, arg1),
+        GETTID => sys_gettid(),
+        WAITTID => sys_waittid(arg0, arg1 as *mut i32),
+        MUTEX_CREATE => sys_mutex_create(arg0),
+        MUTEX_LOCK => sys_mutex_lock(arg0),
+        MUTEX_UNLOCK => sys_mutex_unlock(arg0),
+        SEMAPHORE_CREATE => sys_semaphore_create(arg0),
+        SEMAPHORE_UP => sys_semaphore_up(arg0),
+        SEMAPHORE_DOWN => sys_semaphore_down(arg0),
+        CONDVAR_CREATE => sys_condvar_create(),
+        CONDVAR_SIGNAL => sys_condvar_signal(arg0),
+        CONDVAR_WAIT => sys_condvar_wait(arg0, arg1),
+        ENABLE_DEADLOCK_DETECT => sys_enable_deadlock_detect(arg0),
+        SETPRIORITY => sys_setpriority(arg0, arg1, arg2 as i32 as isize),
+        GETPRIORITY => sys_getpriority(arg0, arg1),
+        REBOOT => sys_reboot(arg0),
+        SHUTDOWN => sys_shutdown(arg0),
+        _ => sys_unsupported(id),
+    };
+    match ret {
+        Ok(ret) => ret as isize,
+        Err(errno) => errno.as_ret(),
+    }
+}
+
+// 未实现的系统调用返回ENOSYS, 如果进程为SIGSYS注册了处理函数, 则同时向其发送SIGSYS
+fn sys_unsupported(id: usize) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    warn!(
+        "[syscall] process {} issued unsupported syscall id {}",
+        task.pid(),
+        id
+    );
+    if task.signal_actions[SIGSYS as usize].handler != 0 {
+        task.signals.insert(SignalFlags::SIGSYS);
     }
+    Err(Errno::ENOSYS)
 }
@@ -1,107 +1,404 @@
+use core::mem::size_of;
+
 use alloc::{string::String, vec::Vec};
 
 use crate::{
-    fs::inode::{OSInode, OpenFlags},
-    mm::page_table::TopLevelEntry,
+    constant::PATH_MAX,
+    fs::{
+        inode::{OSInode, OpenFlags, YFS},
+        File,
+    },
+    mm::{
+        page_table::TopLevelEntry,
+        uaccess::{copy_from_user, copy_str_from_user, copy_to_user},
+    },
     process::{
-        pid::{task_insert, Pid},
+        itimer::ITimerVal,
+        pcb::{ExitStatus, ProcessControlBlock},
+        pid::{task_find, task_insert, Pid},
         processor::PROCESSOR,
-        queue::QUEUE,
+        queue::{NICE_MAX, NICE_MIN, QUEUE},
+        rlimit::{RLimit, RLIMIT_STACK},
+        rusage::{clock_ticks, RUsage, Tms, RUSAGE_CHILDREN, RUSAGE_SELF},
     },
-    timer::get_time_ms,
+    sbi::{reboot, shutdown},
+    timer::{add_timer, get_time, get_time_ms, remove_timer, TimeSpec},
     types::CStr,
 };
 
-pub fn sys_exit(code: i32) -> isize {
+use super::{Errno, SyscallResult};
+
+// 只退出当前线程, 最后一个线程退出时进程才退出
+pub fn sys_exit(code: i32) -> SyscallResult {
     PROCESSOR.exclusive_access().exit_current(code).schedule();
-    0
+    Ok(0)
 }
 
-pub fn sys_yield() -> isize {
+// 退出整个进程, 进程中的其他线程也会退出
+pub fn sys_exit_group(code: i32) -> SyscallResult {
+    PROCESSOR
+        .exclusive_access()
+        .exit_group(ExitStatus::Exited(code))
+        .schedule();
+    Ok(0)
+}
+
+pub fn sys_yield() -> SyscallResult {
     PROCESSOR.exclusive_access().suspend_current().schedule();
-    0
+    Ok(0)
 }
 
-pub fn sys_get_time() -> isize {
-    get_time_ms() as isize
+pub fn sys_get_time() -> SyscallResult {
+    Ok(get_time_ms())
+}
+
+// 被信号打断时返回EINTR, rem非空则写入剩余的时间
+pub fn sys_nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> SyscallResult {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = thread.process();
+    let entry = TopLevelEntry::from_token(task.token());
+    let req = copy_from_user(entry, req)?;
+    if !req.is_valid() {
+        return Err(Errno::EINVAL);
+    }
+    let expire = get_time().saturating_add(req.to_ticks());
+    add_timer(expire, thread);
+    //其他原因的唤醒不算被打断, 继续睡眠
+    let now = loop {
+        let now = get_time();
+        if now >= expire || task.interrupted() {
+            break now;
+        }
+        PROCESSOR.exclusive_access().block_current().schedule();
+    };
+    //定时器可能还没有被check_timers取走
+    remove_timer(thread);
+    if now >= expire {
+        return Ok(0);
+    }
+    if !rem.is_null() {
+        copy_to_user(entry, rem, TimeSpec::from_ticks(expire - now))?;
+    }
+    Err(Errno::EINTR)
+}
+
+pub fn sys_getrlimit(resource: usize, rlim: *mut RLimit) -> SyscallResult {
+    if resource != RLIMIT_STACK {
+        return Err(Errno::EINVAL);
+    }
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    copy_to_user(task.page_table(), rlim, task.stack_rlimit)?;
+    Ok(0)
+}
+
+// 软限制不能超过硬限制, 硬限制只能调低
+// 调低之前已经长出来的栈不会被回收
+pub fn sys_setrlimit(resource: usize, rlim: *const RLimit) -> SyscallResult {
+    if resource != RLIMIT_STACK {
+        return Err(Errno::EINVAL);
+    }
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let new = copy_from_user(task.page_table(), rlim)?;
+    if new.cur > new.max {
+        return Err(Errno::EINVAL);
+    }
+    if new.max > task.stack_rlimit.max {
+        return Err(Errno::EPERM);
+    }
+    task.stack_rlimit = new;
+    Ok(0)
 }
 
-pub fn sys_sbrk(size: isize) -> isize {
+pub fn sys_sbrk(size: isize) -> SyscallResult {
     PROCESSOR
         .exclusive_access()
         .current()
         .unwrap()
-        .change_brk(size) as isize
+        .change_brk(size)
 }
 
-pub fn sys_fork() -> isize {
-    let fork = PROCESSOR.exclusive_access().current().unwrap().fork();
+// 子进程中只有调用fork的线程
+pub fn sys_fork() -> SyscallResult {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let fork = thread.process().fork(thread);
     let pid = unsafe { (*fork).pid() };
-    unsafe {
-        (*fork).trap_ctx().x[10] = 0;
-    }
-    QUEUE.exclusive_access().push(fork);
+    let child = unsafe { (*fork).thread(thread.tid).unwrap() };
+    child.trap_ctx().x[10] = 0;
+    QUEUE.lock().push(child);
     task_insert(pid, fork);
-    pid.0 as isize
+    Ok(pid.0)
 }
 
-pub fn sys_exec(path: CStr, mut args: *const CStr) -> isize {
-    let task = PROCESSOR.exclusive_access().current().unwrap();
-    let entry = task.page_table();
-    let s = entry.translate_virt_str(path);
-    if s == "." {
-        return -1;
-    }
-
-    let mut argv: Vec<String> = Vec::new();
+// 读取以空指针结尾的字符串数组, 字符串和指针占用的空间超过budget时返回E2BIG
+fn copy_strs_from_user(
+    page_table: TopLevelEntry,
+    mut ptrs: *const CStr,
+    budget: &mut usize,
+) -> SyscallResult<Vec<String>> {
+    let mut strs = Vec::new();
     loop {
-        let arg = *entry.translate_virt_ref(args);
-        if arg == core::ptr::null() {
-            break;
+        let ptr = copy_from_user(page_table, ptrs)?;
+        if ptr.is_null() {
+            break Ok(strs);
         }
-        argv.push(entry.translate_virt_str(arg));
-        args = unsafe { args.add(1) };
+        //字符串连同结尾的0最多占用budget中除去指针的部分, 超出时立即停止读入
+        let max_len = budget.checked_sub(size_of::<CStr>()).ok_or(Errno::E2BIG)?;
+        let s = match copy_str_from_user(page_table, ptr, max_len) {
+            Err(Errno::ENAMETOOLONG) => return Err(Errno::E2BIG),
+            s => s?,
+        };
+        *budget -= s.len() + 1 + size_of::<CStr>();
+        strs.push(s);
+        ptrs = unsafe { ptrs.add(1) };
     }
+}
 
-    if let Some(inode) = OSInode::open(&s, OpenFlags::READ) {
-        let data = inode.read_all();
-        task.exec(&data, argv);
-        0
-    } else {
-        -1
+// 解释器最多嵌套这么多层
+const SHEBANG_MAX_DEPTH: usize = 4;
+// #!行的最大长度, 包括换行符
+const SHEBANG_MAX_LEN: usize = 256;
+
+// 解析文件开头的"#!解释器 [参数]", 不是脚本时返回None
+// 解释器后面的内容去掉首尾空白后整体作为一个参数
+fn parse_shebang(data: &[u8]) -> SyscallResult<Option<(&str, Option<&str>)>> {
+    if !data.starts_with(b"#!") {
+        return Ok(None);
+    }
+    let line = data[..data.len().min(SHEBANG_MAX_LEN)]
+        .split(|&c| c == b'\n')
+        .next()
+        .filter(|line| line.len() < SHEBANG_MAX_LEN)
+        .ok_or(Errno::ENOEXEC)?;
+    let line = core::str::from_utf8(&line[2..])
+        .map_err(|_| Errno::ENOEXEC)?
+        .trim_matches(|c| c == ' ' || c == '\t');
+    let (interp, arg) = match line.find(|c| c == ' ' || c == '\t') {
+        Some(idx) => (
+            &line[..idx],
+            Some(line[idx..].trim_matches(|c| c == ' ' || c == '\t')),
+        ),
+        None => (line, None),
+    };
+    if interp.is_empty() {
+        return Err(Errno::ENOEXEC);
     }
+    Ok(Some((interp, arg.filter(|arg| !arg.is_empty()))))
 }
 
-pub fn sys_wait(pid: isize, exit_code: *mut i32) -> isize {
-    let task = PROCESSOR.exclusive_access().current().unwrap();
-    let pid = Pid(pid as usize);
-    //pid不等于-1或者不等于任意一个子进程的pid
-    if !task
-        .children
+// 进程中还有其他线程没有退出时返回EBUSY
+pub fn sys_exec(path: CStr, args: *const CStr, envp: *const CStr) -> SyscallResult {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = thread.process();
+    if task
+        .threads
         .iter()
-        .any(|&p| pid == Pid::ANY || pid == unsafe { &mut *p }.pid())
+        .flatten()
+        .any(|t| t.tid != thread.tid && !t.is_zombie())
     {
-        return -1;
-    }
-
-    if let Some((idx, &child)) = task.children.iter().enumerate().find(|(_, &p)| {
-        let p = unsafe { &mut *p };
-        p.is_zombie() && (pid == Pid::ANY || pid == p.pid())
-    }) {
-        unsafe {
-            task.children.remove(idx);
-            let child_exit_code = (*child).exit_code;
-            let pid = (*child).pid();
-            core::ptr::drop_in_place(child);
-            *TopLevelEntry::from_token(PROCESSOR.exclusive_access().current_token().unwrap())
-                .translate_virt_mut(exit_code) = child_exit_code;
-            pid.0 as isize
+        return Err(Errno::EBUSY);
+    }
+    let entry = task.page_table();
+    let s = copy_str_from_user(entry, path, PATH_MAX)?;
+
+    //这里只是避免复制过多的数据, 加上解释器的参数后在exec中还会检查一次
+    let mut budget = task.arg_max();
+    let mut argv = copy_strs_from_user(entry, args, &mut budget)?;
+    //envp可以为空指针, 表示没有环境变量
+    let envp = if envp.is_null() {
+        Vec::new()
+    } else {
+        copy_strs_from_user(entry, envp, &mut budget)?
+    };
+
+    //脚本文件交给#!指定的解释器执行, 解释器本身也可以是脚本
+    let mut path = s;
+    let mut depth = 0;
+    let data = loop {
+        let inode = OSInode::open(&path, OpenFlags::READ)?;
+        //目录不能执行, 其他不是ELF的文件由加载器返回ENOEXEC
+        if inode.inode().map_or(false, |vnode| vnode.is_dir()) {
+            return Err(Errno::EACCES);
+        }
+        let data = inode.read_all();
+        let (interp, arg) = match parse_shebang(&data)? {
+            Some(shebang) => shebang,
+            None => break data,
+        };
+        if depth == SHEBANG_MAX_DEPTH {
+            return Err(Errno::ELOOP);
+        }
+        depth += 1;
+        //新的argv是: 解释器 [可选参数] 脚本路径 原来的argv[1..]
+        let mut args = Vec::with_capacity(argv.len() + 2);
+        args.push(String::from(interp));
+        args.extend(arg.map(String::from));
+        args.push(core::mem::replace(&mut path, String::from(interp)));
+        args.extend(argv.drain(..).skip(1));
+        argv = args;
+    };
+    let argc = argv.len();
+    task.exec(thread, &data, argv, envp)?;
+    //返回值会写到新程序的a0中
+    Ok(argc)
+}
+
+// waitpid的options, 没有可回收的子进程时立即返回0而不是阻塞
+pub const WNOHANG: usize = 1;
+
+// status非空时写入子进程的状态字, 格式和linux相同
+// 没有符合pid的子进程时返回ECHILD, 子进程都还没有退出时阻塞, 设置了WNOHANG则返回0
+pub fn sys_wait(pid: isize, status: *mut i32, options: usize) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let pid = Pid(pid as usize);
+    loop {
+        //pid不等于-1或者不等于任意一个子进程的pid
+        if !task
+            .children
+            .iter()
+            .any(|&p| pid == Pid::ANY || pid == unsafe { &mut *p }.pid())
+        {
+            return Err(Errno::ECHILD);
+        }
+
+        if let Some((idx, &child)) = task.children.iter().enumerate().find(|(_, &p)| {
+            let p = unsafe { &mut *p };
+            p.is_zombie() && (pid == Pid::ANY || pid == p.pid())
+        }) {
+            unsafe {
+                //先写回状态, 出错时子进程留给下一次wait回收
+                if !status.is_null() {
+                    copy_to_user(
+                        task.page_table(),
+                        status,
+                        (*child).exit_status.wait_status(),
+                    )?;
+                }
+                task.children.remove(idx);
+                task.children_usage += (*child).usage;
+                task.children_usage += (*child).children_usage;
+                let pid = (*child).pid();
+                core::ptr::drop_in_place(child);
+                return Ok(pid.0);
+            }
+        }
+        if options & WNOHANG != 0 {
+            return Ok(0);
         }
+        //被信号打断或者进程正在退出
+        if task.interrupted() {
+            return Err(Errno::EINTR);
+        }
+        //子进程退出时会唤醒父进程, 醒来后重新检查
+        task.wait_queue.wait();
+    }
+}
+
+// 返回系统启动以来的时钟滴答数, buf为空指针时只返回时间
+pub fn sys_times(buf: *mut Tms) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    if !buf.is_null() {
+        let tms = Tms::new(&task.usage, &task.children_usage);
+        copy_to_user(task.page_table(), buf, tms)?;
+    }
+    Ok(clock_ticks(get_time()))
+}
+
+pub fn sys_getrusage(who: isize, usage: *mut RUsage) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let rusage = match who {
+        RUSAGE_SELF => task.usage,
+        RUSAGE_CHILDREN => task.children_usage,
+        _ => return Err(Errno::EINVAL),
+    };
+    copy_to_user(task.page_table(), usage, rusage.into())?;
+    Ok(0)
+}
+
+pub fn sys_getpid() -> SyscallResult {
+    Ok(PROCESSOR.exclusive_access().current().unwrap().pid().0)
+}
+
+// 只支持which为PRIO_PROCESS, who为0时表示当前进程
+const PRIO_PROCESS: usize = 0;
+
+fn priority_target(which: usize, who: usize) -> Result<&'static mut ProcessControlBlock, Errno> {
+    if which != PRIO_PROCESS {
+        return Err(Errno::EINVAL);
+    }
+    if who == 0 {
+        Ok(PROCESSOR.exclusive_access().current().unwrap())
     } else {
-        -2
+        task_find(who)
+            .map(|task| unsafe { &mut *task })
+            .ok_or(Errno::ESRCH)
     }
 }
 
-pub fn sys_getpid() -> isize {
-    PROCESSOR.exclusive_access().current().unwrap().pid().0 as isize
+// 超出范围的nice值会被截断到[-20, 19], 进程中的所有线程都会被修改
+pub fn sys_setpriority(which: usize, who: usize, nice: isize) -> SyscallResult {
+    let task = priority_target(which, who)?;
+    for thread in task.threads.iter_mut().flatten() {
+        thread.sched.nice = nice.clamp(NICE_MIN, NICE_MAX);
+    }
+    Ok(0)
+}
+
+// 和linux一样返回20 - nice, 避免返回负数
+// who为0时返回当前线程的nice值, 否则返回目标进程中第一个线程的nice值
+pub fn sys_getpriority(which: usize, who: usize) -> SyscallResult {
+    let task = priority_target(which, who)?;
+    let nice = if who == 0 {
+        PROCESSOR
+            .exclusive_access()
+            .current_thread()
+            .unwrap()
+            .sched
+            .nice
+    } else {
+        task.threads
+            .iter()
+            .flatten()
+            .next()
+            .ok_or(Errno::ESRCH)?
+            .sched
+            .nice
+    };
+    Ok((20 - nice) as usize)
+}
+
+// failure非0时以失败原因关机
+pub fn sys_shutdown(failure: usize) -> SyscallResult {
+    YFS.flush();
+    shutdown(failure != 0)
+}
+
+// warm非0时热重启, 否则冷重启
+pub fn sys_reboot(warm: usize) -> SyscallResult {
+    YFS.flush();
+    reboot(warm != 0)
+}
+
+// 读取定时器的剩余时间和间隔
+pub fn sys_getitimer(which: usize, curr: *mut ITimerVal) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let value = task.itimers.get(which, get_time())?;
+    copy_to_user(task.page_table(), curr, value)?;
+    Ok(0)
+}
+
+// new.value为0时取消定时器, new.interval为0时只触发一次, old非空时写回原来的设置
+pub fn sys_setitimer(which: usize, new: *const ITimerVal, old: *mut ITimerVal) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let page_table = task.page_table();
+    let new = copy_from_user(page_table, new)?;
+    if !new.is_valid() {
+        return Err(Errno::EINVAL);
+    }
+    let now = get_time();
+    let value = task.itimers.get(which, now)?;
+    if !old.is_null() {
+        copy_to_user(page_table, old, value)?;
+    }
+    task.itimers.set(which, new, now, task.pid());
+    Ok(0)
 }
@@ -1,55 +1,134 @@
-use crate::process::{pid::task_find, processor::PROCESSOR, signal::SignalFlags};
+use crate::{
+    mm::{
+        page_table::TopLevelEntry,
+        uaccess::{copy_from_user, copy_to_user},
+    },
+    process::{
+        pid::task_find,
+        processor::PROCESSOR,
+        signal::{SigActionFlags, SignalAction, SignalFlags, SignalFrame},
+    },
+};
 
-pub fn sys_kill(pid: usize, signal: usize) -> isize {
-    if let (Some(task), Some(signal)) = (task_find(pid), SignalFlags::from_bits(1 << signal)) {
-        let task = unsafe { &mut *task };
-        if task.signals.contains(signal) {
-            return -1;
-        }
-        task.signals.insert(signal);
-        0
-    } else {
-        -1
+use super::{Errno, SyscallResult};
+
+pub fn sys_kill(pid: usize, signal: usize) -> SyscallResult {
+    let task = task_find(pid).ok_or(Errno::ESRCH)?;
+    let signal = SignalFlags::from_bits(1 << signal).ok_or(Errno::EINVAL)?;
+    let task = unsafe { &mut *task };
+    if task.signals.contains(signal) {
+        return Err(Errno::EAGAIN);
     }
+    task.raise(signal);
+    Ok(0)
+}
+
+// sigprocmask的how
+const SIG_BLOCK: usize = 0;
+const SIG_UNBLOCK: usize = 1;
+const SIG_SETMASK: usize = 2;
+
+// 用户态的信号集和linux的sigset_t一样是64位, 第n位表示信号n, 不认识的位被忽略
+// SIGKILL和SIGSTOP不能被屏蔽, 读入时直接去掉
+fn read_sigset(page_table: TopLevelEntry, set: *const u64) -> SyscallResult<SignalFlags> {
+    let bits = copy_from_user(page_table, set)?;
+    Ok(SignalFlags::from_bits_truncate(bits as u32 as i32) - SignalFlags::UNBLOCKABLE)
 }
 
-pub fn sys_sigprocmask(mask: usize) -> isize {
+fn write_sigset(page_table: TopLevelEntry, set: *mut u64, flags: SignalFlags) -> SyscallResult<()> {
+    copy_to_user(page_table, set, flags.bits() as u32 as u64)
+}
+
+// set为空指针时只读取当前的屏蔽字, oldset不为空时写回修改前的屏蔽字
+pub fn sys_sigprocmask(how: usize, set: *const u64, oldset: *mut u64) -> SyscallResult {
     let task = PROCESSOR.exclusive_access().current().unwrap();
+    let page_table = task.page_table();
     let old = task.signal_mask;
-    if let Some(mask) = SignalFlags::from_bits(mask as i32) {
-        task.signal_mask = mask;
-        old.bits() as isize
+    let mask = if set.is_null() {
+        old
     } else {
-        -1
+        let set = read_sigset(page_table, set)?;
+        match how {
+            SIG_BLOCK => old | set,
+            SIG_UNBLOCK => old - set,
+            SIG_SETMASK => set,
+            _ => return Err(Errno::EINVAL),
+        }
+    };
+    if !oldset.is_null() {
+        write_sigset(page_table, oldset, old)?;
     }
+    task.signal_mask = mask;
+    Ok(0)
 }
 
-pub fn sys_sigaction(signal: usize, new_action: usize, old_action: usize) -> isize {
+// 已经收到但被屏蔽的信号
+pub fn sys_sigpending(set: *mut u64) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    write_sigset(task.page_table(), set, task.signals & task.signal_mask)?;
+    Ok(0)
+}
+
+// 临时把屏蔽字换成mask并阻塞, 直到有信号需要处理, 总是返回EINTR
+// 原来的屏蔽字保存在信号帧中, 处理函数返回后恢复; 没有调用处理函数时在返回用户态前恢复
+pub fn sys_sigsuspend(mask: *const u64) -> SyscallResult {
+    let task = PROCESSOR.exclusive_access().current().unwrap();
+    let mask = read_sigset(task.page_table(), mask)?;
+    task.saved_mask = Some(task.signal_mask);
+    task.signal_mask = mask;
+    while !task.interrupted() {
+        //发送信号时会唤醒进程中所有阻塞的线程
+        PROCESSOR.exclusive_access().block_current().schedule();
+    }
+    Err(Errno::EINTR)
+}
+
+pub fn sys_sigaction(signal: usize, new_action: usize, old_action: usize) -> SyscallResult {
     if SignalFlags::from_bits_truncate(1 << signal as i32)
         .intersects(SignalFlags::SIGKILL | SignalFlags::SIGSTOP)
     {
-        return -1;
+        return Err(Errno::EINVAL);
     }
 
     let task = PROCESSOR.exclusive_access().current().unwrap();
     let page_table = task.page_table();
-    let action = match task.signal_actions.get_mut(signal) {
-        Some(action) => action,
-        None => return -1,
+    let action = task.signal_actions.get_mut(signal).ok_or(Errno::EINVAL)?;
+    //先读出新的action, 出错时什么也不修改
+    let new = if new_action != 0 {
+        Some(copy_from_user(
+            page_table,
+            new_action as *const SignalAction,
+        )?)
+    } else {
+        None
     };
     if old_action != 0 {
-        *page_table.translate_virt_mut(old_action as *mut _) = *action;
+        copy_to_user(page_table, old_action as *mut SignalAction, *action)?;
     }
-    if new_action != 0 {
-        *action = *page_table.translate_virt_ref(new_action as *const _);
+    if let Some(mut new) = new {
+        //不认识的标志被忽略
+        new.flags = SigActionFlags::from_bits_truncate(new.flags.bits());
+        *action = new;
     }
-    0
+    Ok(0)
 }
 
-pub fn sys_sigret() -> isize {
-    let task = PROCESSOR.exclusive_access().current().unwrap();
-    task.handling_sig = None;
-    let trap_ctx = task.trap_ctx();
-    *trap_ctx = task.trap_ctx_backup;
-    0
+// 信号处理函数返回到sigreturn跳板时sp指向信号帧, 从中恢复被打断时的状态
+// 返回值会写到a0, 所以返回保存的a0
+pub fn sys_sigret() -> SyscallResult {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = thread.process();
+    let cx = thread.trap_ctx();
+    let frame = match copy_from_user(task.page_table(), cx.x[2] as *const SignalFrame) {
+        Ok(frame) => frame,
+        Err(_) => {
+            task.segv(cx.x[2]);
+            return Err(Errno::EFAULT);
+        }
+    };
+    //sstatus等内核的部分保持不变, 用户态不能借此提升特权级
+    cx.x = frame.x;
+    cx.sepc = frame.sepc;
+    task.signal_mask = frame.mask - SignalFlags::UNBLOCKABLE;
+    Ok(frame.x[10])
 }
@@ -0,0 +1,197 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    mm::uaccess::copy_from_user,
+    process::{pcb::ProcessControlBlock, processor::PROCESSOR},
+    sync::{
+        condvar::Condvar,
+        deadlock::Resource,
+        futex::{futex_wait, futex_wake, FUTEX_PRIVATE_FLAG, FUTEX_WAIT, FUTEX_WAKE},
+        mutex::{BlockingMutex, Mutex, SpinMutex},
+        semaphore::Semaphore,
+    },
+    timer::{get_time, TimeSpec},
+};
+
+use super::{Errno, SyscallResult};
+
+// 放到第一个空位上, 返回下标
+fn insert<T>(list: &mut Vec<Option<T>>, item: T) -> usize {
+    if let Some(id) = list.iter().position(|slot| slot.is_none()) {
+        list[id] = Some(item);
+        id
+    } else {
+        list.push(Some(item));
+        list.len() - 1
+    }
+}
+
+fn current() -> (&'static mut ProcessControlBlock, usize) {
+    let thread = PROCESSOR.exclusive_access().current_thread().unwrap();
+    (thread.process(), thread.tid)
+}
+
+// blocking为0时创建自旋锁, 否则创建阻塞锁
+pub fn sys_mutex_create(blocking: usize) -> SyscallResult {
+    let (task, _) = current();
+    let mutex: Box<dyn Mutex> = if blocking == 0 {
+        Box::new(SpinMutex::new())
+    } else {
+        Box::new(BlockingMutex::new())
+    };
+    let id = insert(&mut task.mutex_list, mutex);
+    task.deadlock_detector.add_resource(Resource::Mutex(id), 1);
+    Ok(id)
+}
+
+// check为false时不做死锁检测, 用于条件变量醒来后重新获取锁
+fn mutex_lock(id: usize, check: bool) -> SyscallResult {
+    let (task, tid) = current();
+    let mutex = task
+        .mutex_list
+        .get_mut(id)
+        .and_then(|mutex| mutex.as_deref_mut())
+        .ok_or(Errno::EINVAL)?;
+    let res = Resource::Mutex(id);
+    let detector = &mut task.deadlock_detector;
+    if check && !detector.request(tid, res) {
+        return Err(Errno::EDEADLK);
+    }
+    match mutex.lock() {
+        Ok(()) => {
+            detector.acquire(tid, res);
+            Ok(0)
+        }
+        Err(errno) => {
+            detector.cancel(tid, res);
+            Err(errno)
+        }
+    }
+}
+
+// 开启死锁检测时, 会导致死锁的申请返回EDEADLK
+pub fn sys_mutex_lock(id: usize) -> SyscallResult {
+    mutex_lock(id, true)
+}
+
+// 不是持有者的线程解锁时返回EPERM, 死锁检测的状态不变
+pub fn sys_mutex_unlock(id: usize) -> SyscallResult {
+    let (task, tid) = current();
+    let mutex = task
+        .mutex_list
+        .get_mut(id)
+        .and_then(|mutex| mutex.as_deref_mut())
+        .ok_or(Errno::EINVAL)?;
+    mutex.unlock()?;
+    task.deadlock_detector.release(tid, Resource::Mutex(id));
+    Ok(0)
+}
+
+pub fn sys_semaphore_create(count: usize) -> SyscallResult {
+    let (task, _) = current();
+    let id = insert(&mut task.semaphore_list, Box::new(Semaphore::new(count)));
+    task.deadlock_detector
+        .add_resource(Resource::Semaphore(id), count);
+    Ok(id)
+}
+
+pub fn sys_semaphore_up(id: usize) -> SyscallResult {
+    let (task, tid) = current();
+    let semaphore = task
+        .semaphore_list
+        .get_mut(id)
+        .and_then(|semaphore| semaphore.as_deref_mut())
+        .ok_or(Errno::EINVAL)?;
+    semaphore.up();
+    task.deadlock_detector.release(tid, Resource::Semaphore(id));
+    Ok(0)
+}
+
+// 开启死锁检测时, 会导致死锁的申请返回EDEADLK
+pub fn sys_semaphore_down(id: usize) -> SyscallResult {
+    let (task, tid) = current();
+    let semaphore = task
+        .semaphore_list
+        .get_mut(id)
+        .and_then(|semaphore| semaphore.as_deref_mut())
+        .ok_or(Errno::EINVAL)?;
+    let res = Resource::Semaphore(id);
+    let detector = &mut task.deadlock_detector;
+    if !detector.request(tid, res) {
+        return Err(Errno::EDEADLK);
+    }
+    match semaphore.down() {
+        Ok(()) => {
+            detector.acquire(tid, res);
+            Ok(0)
+        }
+        Err(errno) => {
+            detector.cancel(tid, res);
+            Err(errno)
+        }
+    }
+}
+
+pub fn sys_condvar_create() -> SyscallResult {
+    let (task, _) = current();
+    Ok(insert(&mut task.condvar_list, Box::new(Condvar::new())))
+}
+
+pub fn sys_condvar_signal(id: usize) -> SyscallResult {
+    let (task, _) = current();
+    task.condvar_list
+        .get_mut(id)
+        .and_then(|condvar| condvar.as_deref_mut())
+        .ok_or(Errno::EINVAL)?
+        .signal();
+    Ok(0)
+}
+
+// 释放mutex后睡眠, 返回前重新获取mutex
+pub fn sys_condvar_wait(id: usize, mutex_id: usize) -> SyscallResult {
+    let (task, _) = current();
+    let condvar = task
+        .condvar_list
+        .get_mut(id)
+        .and_then(|condvar| condvar.as_deref_mut())
+        .ok_or(Errno::EINVAL)?;
+    sys_mutex_unlock(mutex_id)?;
+    condvar.wait();
+    mutex_lock(mutex_id, false)
+}
+
+// enabled为1时开启死锁检测, 为0时关闭
+pub fn sys_enable_deadlock_detect(enabled: usize) -> SyscallResult {
+    let (task, _) = current();
+    match enabled {
+        0 => task.deadlock_detector.enabled = false,
+        1 => task.deadlock_detector.enabled = true,
+        _ => return Err(Errno::EINVAL),
+    }
+    Ok(0)
+}
+
+// FUTEX_WAIT: uaddr处的值等于val时阻塞, timeout非空时是相对的超时时间
+// FUTEX_WAKE: 唤醒最多val个阻塞在uaddr上的线程, 返回唤醒的个数
+pub fn sys_futex(uaddr: usize, op: usize, val: u32, timeout: *const TimeSpec) -> SyscallResult {
+    if uaddr % core::mem::size_of::<u32>() != 0 {
+        return Err(Errno::EINVAL);
+    }
+    match op & !FUTEX_PRIVATE_FLAG {
+        FUTEX_WAIT => {
+            let expire = if timeout.is_null() {
+                None
+            } else {
+                let (task, _) = current();
+                let timeout = copy_from_user(task.page_table(), timeout)?;
+                if !timeout.is_valid() {
+                    return Err(Errno::EINVAL);
+                }
+                Some(get_time().saturating_add(timeout.to_ticks()))
+            };
+            futex_wait(uaddr, val, expire)
+        }
+        FUTEX_WAKE => futex_wake(uaddr, val as usize),
+        _ => Err(Errno::ENOSYS),
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    mm::{mem_set::kernel_token, uaccess::copy_to_user},
+    process::{processor::PROCESSOR, queue::QUEUE, tcb::ThreadControlBlock},
+    trap::{context::Context as TrapContext, trap_handler},
+};
+
+use super::{Errno, SyscallResult};
+
+// 在当前进程中创建一个从entry开始执行的线程, arg通过a0传入, 返回新线程的tid
+pub fn sys_thread_create(entry: usize, arg: usize) -> SyscallResult {
+    let current = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = current.process();
+    let tid = task.tid_allocator.alloc();
+    let user_stack = task.alloc_user_stack(tid);
+    let trap_ctx_ppn = task.alloc_trap_ctx(tid);
+    let thread = ThreadControlBlock::new(
+        task,
+        tid,
+        trap_ctx_ppn,
+        Some(user_stack),
+        current.sched.inherit(),
+    );
+    *thread.trap_ctx() = TrapContext::new(
+        entry,
+        user_stack.end.floor().0,
+        kernel_token(),
+        thread.kernel_stack.btm().0,
+        trap_handler as usize,
+    );
+    thread.trap_ctx().x[10] = arg;
+    let thread = task.add_thread(thread);
+    QUEUE.lock().push(thread);
+    Ok(tid)
+}
+
+pub fn sys_gettid() -> SyscallResult {
+    Ok(PROCESSOR.exclusive_access().current_thread().unwrap().tid)
+}
+
+// 等待同一进程中的线程tid退出并回收它, 成功时返回tid
+pub fn sys_waittid(tid: usize, exit_code: *mut i32) -> SyscallResult {
+    let current = PROCESSOR.exclusive_access().current_thread().unwrap();
+    let task = current.process();
+    if tid == current.tid {
+        return Err(Errno::EDEADLK);
+    }
+    loop {
+        let thread = task.thread(tid).ok_or(Errno::ESRCH)?;
+        if thread.is_zombie() {
+            if !exit_code.is_null() {
+                copy_to_user(task.page_table(), exit_code, thread.exit_code)?;
+            }
+            task.threads[tid] = None;
+            task.tid_allocator.dealloc(tid);
+            return Ok(tid);
+        }
+        //被信号打断或者进程正在退出
+        if task.interrupted() {
+            return Err(Errno::EINTR);
+        }
+        //线程退出时会唤醒等待队列, 醒来后重新检查
+        task.thread_wait_queue.wait();
+    }
+}
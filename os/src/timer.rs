@@ -1,15 +1,28 @@
-use crate::{constant::CLOCK_FREQ, sbi::set_timer};
+use core::cmp::Ordering;
+
+use crate::{
+    constant::CLOCK_FREQ,
+    process::{itimer::alarm, pid::Pid, tcb::ThreadControlBlock, wait_queue::wakeup},
+    sbi::set_timer,
+    sync::spin::SpinLock,
+};
+use alloc::{collections::BinaryHeap, vec::Vec};
 use riscv::register::time;
 
 const TICKS_PER_SEC: usize = 100;
 const MILLIS_PER_SEC: usize = 1000;
+const MICROS_PER_SEC: usize = 1_000_000;
+const NANOS_PER_SEC: usize = 1_000_000_000;
 
 pub fn get_time() -> usize {
     time::read()
 }
 
-pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC)
+// 默认的时间片长度, 以时钟周期计
+pub const TIME_SLICE: usize = CLOCK_FREQ / TICKS_PER_SEC;
+
+pub fn set_next_trigger(ticks: usize) {
+    set_timer(get_time() + ticks)
 }
 
 pub fn get_time_ms() -> usize {
@@ -21,3 +34,151 @@ pub fn init() {
         riscv::register::sie::set_stimer();
     }
 }
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+impl TimeSpec {
+    pub fn is_valid(&self) -> bool {
+        self.nsec < NANOS_PER_SEC
+    }
+
+    //sec来自用户, 太大时饱和到usize::MAX
+    pub fn to_ticks(&self) -> usize {
+        self.sec
+            .saturating_mul(CLOCK_FREQ)
+            .saturating_add(self.nsec * CLOCK_FREQ / NANOS_PER_SEC)
+    }
+
+    pub fn from_ticks(ticks: usize) -> Self {
+        Self {
+            sec: ticks / CLOCK_FREQ,
+            nsec: ticks % CLOCK_FREQ * NANOS_PER_SEC / CLOCK_FREQ,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+impl TimeVal {
+    pub fn is_valid(&self) -> bool {
+        self.usec < MICROS_PER_SEC
+    }
+
+    //sec来自用户, 太大时饱和到usize::MAX
+    pub fn to_ticks(&self) -> usize {
+        self.sec
+            .saturating_mul(CLOCK_FREQ)
+            .saturating_add(self.usec * CLOCK_FREQ / MICROS_PER_SEC)
+    }
+
+    pub fn from_ticks(ticks: usize) -> Self {
+        Self {
+            sec: ticks / CLOCK_FREQ,
+            usec: ticks % CLOCK_FREQ * MICROS_PER_SEC / CLOCK_FREQ,
+        }
+    }
+}
+
+enum Target {
+    // 唤醒睡眠的线程
+    Thread(*mut ThreadControlBlock),
+    // 进程的ITIMER_REAL到期, 发送SIGALRM
+    Alarm(Pid),
+}
+
+// 到期时间以时钟周期计
+struct Timer {
+    expire: usize,
+    target: Target,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.expire == other.expire
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// BinaryHeap是大根堆, 反过来比较使最早到期的定时器在堆顶
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.expire.cmp(&self.expire)
+    }
+}
+
+lazy_static! {
+    static ref TIMERS: SpinLock<BinaryHeap<Timer>> = SpinLock::new(BinaryHeap::new());
+}
+
+// 在expire时刻唤醒task, task需要自己进入阻塞状态
+pub fn add_timer(expire: usize, task: *mut ThreadControlBlock) {
+    TIMERS.lock().push(Timer {
+        expire,
+        target: Target::Thread(task),
+    });
+}
+
+// 在expire时刻向进程pid发送SIGALRM, 修改定时器前要用remove_alarm撤销旧的记录
+pub fn add_alarm(expire: usize, pid: Pid) {
+    TIMERS.lock().push(Timer {
+        expire,
+        target: Target::Alarm(pid),
+    });
+}
+
+// 进程重新设置或者取消ITIMER_REAL时撤销之前登记的到期时刻
+pub fn remove_alarm(pid: Pid) {
+    let mut timers = TIMERS.lock();
+    *timers = core::mem::take(&mut *timers)
+        .into_iter()
+        .filter(|timer| !matches!(timer.target, Target::Alarm(p) if p == pid))
+        .collect();
+}
+
+// 进程提前被唤醒或者退出时撤销它的定时器
+pub fn remove_timer(task: *mut ThreadControlBlock) {
+    let mut timers = TIMERS.lock();
+    *timers = core::mem::take(&mut *timers)
+        .into_iter()
+        .filter(|timer| !matches!(timer.target, Target::Thread(t) if t == task))
+        .collect();
+}
+
+// 唤醒所有已到期的定时器上的进程
+pub fn check_timers() {
+    let now = get_time();
+    let mut expired = Vec::new();
+    {
+        let mut timers = TIMERS.lock();
+        while let Some(timer) = timers.peek() {
+            if timer.expire > now {
+                break;
+            }
+            expired.extend(timers.pop());
+        }
+    }
+    //周期定时器到期时会重新加入TIMERS, 不能持有锁
+    for timer in expired {
+        match timer.target {
+            Target::Thread(task) => wakeup(task),
+            Target::Alarm(pid) => alarm(pid, timer.expire),
+        }
+    }
+}
@@ -15,6 +15,8 @@ pub struct Context {
     pub kernel_sp: usize,
     // 内核空间中trap_handler的入口地址
     pub trap_handler: usize,
+    // 返回用户态前所在hart的id, 下一次trap时恢复到tp中
+    pub hart_id: usize,
 }
 
 impl Context {
@@ -39,6 +41,7 @@ impl Context {
             kernel_satp,  // addr of page table
             kernel_sp,    // kernel stack
             trap_handler, // addr of trap_handler function
+            hart_id: 0,   // filled by __restore
         };
         cx.set_sp(sp); // app's user stack pointer
         cx // return initial Trap Context of app
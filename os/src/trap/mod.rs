@@ -2,12 +2,14 @@
 use crate::{
     constant::{
         exit_code::{ILLEGAL_INSTRUCTION, LOAD_STORE_FAULT},
-        TRAMPOLINE_VA, TRAP_CONTEXT_VA,
+        TRAMPOLINE_VA,
     },
     mm::address::VirtAddr,
     process::{processor::PROCESSOR, signal::SignalFlags},
     sbi::shutdown,
+    sync::spin::KERNEL_LOCK,
     syscall::syscall,
+    timer::check_timers,
 };
 
 use self::context::Context;
@@ -44,6 +46,9 @@ pub fn trap_from_kernel() -> ! {
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    PROCESSOR.exclusive_access().trap_enter();
+    KERNEL_LOCK.acquire();
+    PROCESSOR.exclusive_access().account_user();
     let cx = PROCESSOR.exclusive_access().current_trap_ctx().unwrap();
     let scause = scause::read();
     let stval = stval::read();
@@ -54,7 +59,8 @@ pub fn trap_handler() -> ! {
     match scause.cause() {
         Interrupt(i) => match i {
             SupervisorTimer => {
-                PROCESSOR.exclusive_access().suspend_current().schedule();
+                check_timers();
+                PROCESSOR.exclusive_access().preempt_current().schedule();
             }
             _ => panic!(
                 "[trap-handler] unsupported interrupt: {:?}, scause: {:#x}, stval: {:#x}",
@@ -66,15 +72,39 @@ pub fn trap_handler() -> ! {
         Exception(e) => match e {
             UserEnvCall => {
                 let id = cx.x[17];
-                let args = [cx.x[10], cx.x[11], cx.x[12]];
+                let args = [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]];
                 cx.sepc += 4;
-                cx.x[10] = syscall(id, args) as usize;
+                let ret = syscall(id, args);
+                //exec会释放旧的trap上下文, 要重新获取
+                let cx = PROCESSOR.exclusive_access().current_trap_ctx().unwrap();
+                cx.x[10] = ret as usize;
             }
             IllegalInstruction => {
                 task.signals.insert(SignalFlags::SIGILL);
             }
-            StorePageFault | StoreFault | LoadFault | LoadPageFault => {
-                task.signals.insert(SignalFlags::SIGSEGV);
+            StorePageFault | LoadPageFault => {
+                task.usage.page_faults += 1;
+                let write = matches!(e, StorePageFault);
+                let stack_limit = task.stack_rlimit.cur;
+                if !task
+                    .mem_set
+                    .handle_page_fault(VirtAddr(stval), write, stack_limit)
+                {
+                    task.segv(stval);
+                }
+            }
+            InstructionPageFault => {
+                task.usage.page_faults += 1;
+                let stack_limit = task.stack_rlimit.cur;
+                if !task
+                    .mem_set
+                    .handle_page_fault(VirtAddr(stval), false, stack_limit)
+                {
+                    task.segv(stval);
+                }
+            }
+            StoreFault | LoadFault | InstructionFault => {
+                task.segv(stval);
             }
             _ => panic!(
                 "[trap-handler] unsupported exception: {:?}, scause: {:#x}, stval: {:#x}",
@@ -91,8 +121,22 @@ pub fn trap_handler() -> ! {
 #[no_mangle]
 pub fn trap_return() -> ! {
     set_user_trap_entry();
-    let VirtAddr(trap_cx_ptr) = TRAP_CONTEXT_VA;
+    let process = PROCESSOR.exclusive_access().current().unwrap();
+    //进程正在退出, 其余线程不再回到用户态
+    if process.exiting {
+        PROCESSOR
+            .exclusive_access()
+            .exit_current(process.exit_status.code())
+            .schedule();
+    }
+    let VirtAddr(trap_cx_ptr) = PROCESSOR
+        .exclusive_access()
+        .current_thread()
+        .unwrap()
+        .trap_ctx_va();
     let user_satp = PROCESSOR.exclusive_access().current_token().unwrap();
+    PROCESSOR.exclusive_access().trap_exit();
+    KERNEL_LOCK.release();
     extern "C" {
         fn __alltraps();
         fn __restore();
@@ -31,6 +31,8 @@ __alltraps:
     # read user stack from sscratch and save it in TrapContext
     csrr t2, sscratch
     sd t2, 2*8(sp)
+    # restore hart id into tp, user space may have changed it
+    ld tp, 37*8(sp)
     # load kernel_satp into t0
     ld t0, 34*8(sp)
     # load trap_handler into t1
@@ -51,6 +53,8 @@ __restore:
     csrw sscratch, a0
     mv sp, a0
     # now sp points to TrapContext in user space, start restoring based on it
+    # remember the current hart, the task may be scheduled on another one next time
+    sd tp, 37*8(sp)
     # restore sstatus/sepc
     ld t0, 32*8(sp)
     ld t1, 33*8(sp)
@@ -67,3 +71,12 @@ __restore:
     # back to user stack
     ld sp, 2*8(sp)
     sret
+
+    # signal handlers return here, the page is mapped into every user space
+    .section .text.sigreturn
+    .globl __sigreturn
+    .align 2
+__sigreturn:
+    # SIGRET
+    li a7, 139
+    ecall
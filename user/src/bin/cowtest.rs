@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{exit, fork, waitpid, ExitStatus, ForkResult};
+
+const LEN: usize = 4096 * 4;
+
+static mut DATA: [u8; LEN] = [1; LEN];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    match fork() {
+        ForkResult::Child => {
+            //子进程的写入不能被父进程看到
+            unsafe {
+                assert!(DATA.iter().all(|&b| b == 1));
+                DATA.iter_mut().for_each(|b| *b = 2);
+                assert!(DATA.iter().all(|&b| b == 2));
+            }
+            exit(0)
+        }
+        ForkResult::Parent(pid) => {
+            let (_, exit_code) = waitpid(pid).unwrap();
+            assert_eq!(exit_code, ExitStatus::Exited(0));
+            unsafe {
+                assert!(DATA.iter().all(|&b| b == 1));
+                //父进程写入后子进程已经退出, 物理页只剩一个引用
+                DATA[0] = 3;
+                assert_eq!(DATA[0], 3);
+            }
+        }
+    }
+    println!("cowtest pass.");
+    0
+}
@@ -2,12 +2,11 @@
 #![no_main]
 
 use ylib::{
-    getpid, kill, println, sig_ret, sig_setaction, types::Argv, SignalAction, SignalFlags, SIGUSR2,
+    getpid, kill, println, sig_setaction, types::Argv, Signal, SignalAction, SignalFlags, SIGUSR2,
 };
 
-fn action() -> ! {
-    println!("from signal handler");
-    sig_ret();
+extern "C" fn action(signal: Signal) {
+    println!("from signal handler {}", signal);
 }
 
 #[no_mangle]
@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::{ptr::null, slice};
+use ylib::{
+    exec, fork, get_stack_limit, mmap_anonymous, set_stack_limit, types::Argv, waitpid, Errno,
+    ExitStatus, ForkResult, MmapFlags, ProtFlags, RLimit,
+};
+
+const STACK_LIMIT: usize = 256 * 1024;
+// 栈rlimit为256KiB时参数最多占用128KiB
+const FITS: usize = 120 * 1024;
+const TOO_BIG: usize = 200 * 1024;
+
+// 用户堆太小, 长参数放在匿名映射里
+fn long_arg(len: usize) -> &'static [u8] {
+    let addr = mmap_anonymous(
+        len + 1,
+        ProtFlags::READ | ProtFlags::WRITE,
+        MmapFlags::PRIVATE,
+    )
+    .unwrap();
+    let buf = unsafe { slice::from_raw_parts_mut(addr as *mut u8, len + 1) };
+    buf[..len].fill(b'y');
+    buf
+}
+
+#[no_mangle]
+pub fn main(argv: &Argv) -> i32 {
+    if argv.get(1) == Some(&"child") {
+        assert_eq!(argv[2].len(), FITS);
+        assert!(argv[2].bytes().all(|c| c == b'y'));
+        return 0;
+    }
+    let max = get_stack_limit().unwrap().max;
+    set_stack_limit(RLimit {
+        cur: STACK_LIMIT,
+        max,
+    })
+    .unwrap();
+
+    let pid = match fork() {
+        ForkResult::Child => {
+            let arg = long_arg(FITS);
+            let errno = exec(
+                "e2bigtest\0",
+                &[
+                    "e2bigtest\0".as_ptr(),
+                    "child\0".as_ptr(),
+                    arg.as_ptr(),
+                    null(),
+                ],
+            );
+            panic!("exec with {} bytes of arguments failed: {}", FITS, errno);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+
+    //失败后当前程序还在运行
+    let arg = long_arg(TOO_BIG);
+    assert_eq!(
+        exec(
+            "e2bigtest\0",
+            &["e2bigtest\0".as_ptr(), arg.as_ptr(), null()]
+        ),
+        Errno::E2BIG
+    );
+    println!("e2bigtest pass.");
+    0
+}
@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{
+    exit, fork, make_pipe,
+    syscall::{sys_exec, sys_open, sys_pipe, sys_read, sys_waitpid, sys_write},
+    waitpid, Errno, ExitStatus, ForkResult,
+};
+
+// 没有映射的地址, 内核地址, 以及只读的.rodata
+const UNMAPPED: usize = 0x8;
+const KERNEL: usize = 0xffff_ffff_ffff_f000;
+static READONLY: [u8; 8] = [0; 8];
+// 比PATH_MAX长且没有结尾的0, 内核读到PATH_MAX就要停下
+static LONG_PATH: [u8; 5000] = [b'a'; 5000];
+
+fn check(ret: isize) {
+    assert_eq!(Errno::check(ret), Err(Errno::EFAULT));
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check(sys_open(UNMAPPED, 0));
+    check(sys_open(KERNEL, 0));
+    check(sys_exec(UNMAPPED, 0, 0));
+    check(sys_pipe(UNMAPPED));
+    check(sys_pipe(READONLY.as_ptr() as usize));
+    let long_path = LONG_PATH.as_ptr() as usize;
+    assert_eq!(
+        Errno::check(sys_open(long_path, 0)),
+        Err(Errno::ENAMETOOLONG)
+    );
+    assert_eq!(
+        Errno::check(sys_exec(long_path, 0, 0)),
+        Err(Errno::ENAMETOOLONG)
+    );
+
+    let [read_fd, write_fd] = make_pipe().unwrap();
+    check(sys_write(write_fd, UNMAPPED, 8));
+    check(sys_write(write_fd, KERNEL, 8));
+    assert_eq!(sys_write(write_fd, READONLY.as_ptr() as usize, 8), 8);
+    //往只读的内存里读会失败
+    check(sys_read(read_fd, READONLY.as_ptr() as usize, 8));
+
+    //退出码写不回去时子进程不会被回收
+    let pid = match fork() {
+        ForkResult::Child => exit(7),
+        ForkResult::Parent(pid) => pid,
+    };
+    loop {
+        match Errno::check(sys_waitpid(pid, UNMAPPED, 0)) {
+            Err(Errno::EINTR) => continue,
+            ret => {
+                assert_eq!(ret, Err(Errno::EFAULT));
+                break;
+            }
+        }
+    }
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(7)));
+    println!("efaulttest pass.");
+    0
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::ptr::null;
+use ylib::{exec, fclose, fopen, fread, fwrite, Errno, OpenFlags};
+
+fn read_file(path: &str) -> Vec<u8> {
+    let fd = fopen(path.as_ptr(), OpenFlags::READ).unwrap();
+    let mut data = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        let len = fread(fd, &mut buf).unwrap();
+        if len == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..len]);
+    }
+    fclose(fd).unwrap();
+    data
+}
+
+fn write_file(path: &str, data: &[u8]) {
+    let fd = fopen(
+        path.as_ptr(),
+        OpenFlags::CREATE | OpenFlags::WRITE | OpenFlags::TRUNC,
+    )
+    .unwrap();
+    fwrite(fd, data).unwrap();
+    fclose(fd).unwrap();
+}
+
+fn read_u64(data: &[u8], offset: usize) -> usize {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes) as usize
+}
+
+// 所有PT_LOAD段在文件中的结束位置
+fn load_end(elf: &[u8]) -> usize {
+    let ph_offset = read_u64(elf, 32);
+    let ph_count = u16::from_le_bytes([elf[56], elf[57]]) as usize;
+    (0..ph_count)
+        .map(|i| &elf[ph_offset + i * 56..][..56])
+        .filter(|ph| ph[0] == 1)
+        .map(|ph| read_u64(ph, 8) + read_u64(ph, 32))
+        .max()
+        .unwrap()
+}
+
+// exec失败后当前程序要能继续运行
+fn check(path: &str, data: &[u8], errno: Errno) {
+    write_file(path, data);
+    assert_eq!(exec(path, &[path.as_ptr(), null()]), errno);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let echo = read_file("echo\0");
+    check("elf_text\0", b"#not an elf\n", Errno::ENOEXEC);
+    check("elf_empty\0", b"", Errno::ENOEXEC);
+    check("elf_header\0", &echo[..64], Errno::ENOEXEC);
+    check(
+        "elf_truncated\0",
+        &echo[..load_end(&echo) - 1],
+        Errno::ENOEXEC,
+    );
+    //32位的ELF
+    let mut class = echo.clone();
+    class[4] = 1;
+    check("elf_class\0", &class, Errno::ENOEXEC);
+    //x86_64
+    let mut machine = echo.clone();
+    machine[18] = 62;
+    machine[19] = 0;
+    check("elf_machine\0", &machine, Errno::ENOEXEC);
+    assert_eq!(exec(".\0", &[null()]), Errno::EACCES);
+    println!("elftest pass.");
+    0
+}
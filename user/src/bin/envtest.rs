@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::ptr::null;
+use ylib::{
+    env, exec, execve, fork, getauxval, getenv, setenv, types::Argv, unsetenv, waitpid, Errno,
+    ExitStatus, ForkResult, AT_ENTRY, AT_PAGESZ, AT_RANDOM,
+};
+
+// exec之后的子进程, 检查继承下来的环境变量
+fn child(mode: &str) -> i32 {
+    match mode {
+        "inherit" => {
+            assert_eq!(getenv("ENVTEST").as_deref(), Some("forked value"));
+            assert_eq!(getenv("ENVTEST_EMPTY").as_deref(), Some(""));
+            assert_eq!(getenv("ENVTEST_REMOVED"), None);
+        }
+        "explicit" => {
+            let vars = env();
+            assert_eq!(vars.len(), 1);
+            assert_eq!((vars[0].0.as_str(), vars[0].1.as_str()), ("ONLY", "1=2"));
+        }
+        _ => return 1,
+    }
+    0
+}
+
+fn run(f: impl FnOnce() -> Errno) {
+    let pid = match fork() {
+        ForkResult::Child => panic!("exec envtest failed: {}", f()),
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+}
+
+#[no_mangle]
+pub fn main(argv: &Argv) -> i32 {
+    if let Some(mode) = argv.get(1) {
+        return child(mode);
+    }
+    assert_eq!(getauxval(AT_PAGESZ), Some(4096));
+    assert_ne!(getauxval(AT_ENTRY), None);
+    assert_ne!(getauxval(AT_RANDOM), None);
+
+    assert_eq!(setenv("", "x"), Err(Errno::EINVAL));
+    assert_eq!(setenv("A=B", "x"), Err(Errno::EINVAL));
+    setenv("ENVTEST", "value").unwrap();
+    setenv("ENVTEST_EMPTY", "").unwrap();
+    setenv("ENVTEST_REMOVED", "value").unwrap();
+    assert_eq!(getenv("ENVTEST").as_deref(), Some("value"));
+    unsetenv("ENVTEST_REMOVED").unwrap();
+    assert_eq!(getenv("ENVTEST_REMOVED"), None);
+
+    //fork出的子进程继承环境变量, exec默认把它们传给新程序
+    setenv("ENVTEST", "forked value").unwrap();
+    run(|| {
+        exec(
+            "envtest\0",
+            &["envtest\0".as_ptr(), "inherit\0".as_ptr(), null()],
+        )
+    });
+    //execve只传入指定的环境变量
+    run(|| {
+        execve(
+            "envtest\0",
+            &["envtest\0".as_ptr(), "explicit\0".as_ptr(), null()],
+            &["ONLY=1=2\0".as_ptr(), null()],
+        )
+    });
+    println!("envtest pass.");
+    0
+}
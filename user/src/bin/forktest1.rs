@@ -22,8 +22,8 @@ pub fn main() -> i32 {
         }
     }
     for _ in 0..MAX_CHILD {
-        let (pid, exit_code) = wait();
-        println!("child {} exited with code {}", pid, exit_code);
+        let (pid, status) = wait().unwrap();
+        println!("child {} {}", pid, status);
     }
     println!("forktest pass.");
     0
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU32;
+use ylib::{
+    futex::{futex_wait, Condvar, Mutex},
+    thread_create, time, waittid, yield_, Errno, TimeSpec,
+};
+
+const THREADS: usize = 4;
+const ROUNDS: usize = 200;
+
+static COUNTER: Mutex<usize> = Mutex::new(0);
+static QUEUE: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+static NOT_EMPTY: Condvar = Condvar::new();
+
+#[no_mangle]
+pub fn main() -> i32 {
+    //值不相等时立即返回
+    let word = AtomicU32::new(1);
+    assert_eq!(futex_wait(&word, 0, None), Err(Errno::EAGAIN));
+    let start = time();
+    assert_eq!(
+        futex_wait(&word, 1, Some(&TimeSpec::from_ms(100))),
+        Err(Errno::ETIMEDOUT)
+    );
+    assert!(time() - start >= 100);
+    println!("futex wait ok");
+
+    let tids: Vec<_> = (0..THREADS)
+        .map(|_| {
+            thread_create(|| {
+                for _ in 0..ROUNDS {
+                    let mut counter = COUNTER.lock();
+                    let old = *counter;
+                    yield_();
+                    *counter = old + 1;
+                }
+                0
+            })
+            .unwrap()
+        })
+        .collect();
+    for tid in tids {
+        waittid(tid).unwrap();
+    }
+    assert_eq!(*COUNTER.lock(), THREADS * ROUNDS);
+    println!("futex mutex ok");
+
+    let consumer = thread_create(|| {
+        let mut sum = 0;
+        for _ in 0..ROUNDS {
+            let mut queue = QUEUE.lock();
+            while queue.is_empty() {
+                queue = NOT_EMPTY.wait(queue);
+            }
+            sum += queue.remove(0);
+        }
+        assert_eq!(sum, (0..ROUNDS).sum::<usize>());
+        0
+    })
+    .unwrap();
+    for i in 0..ROUNDS {
+        QUEUE.lock().push(i);
+        NOT_EMPTY.notify_one();
+    }
+    waittid(consumer).unwrap();
+    println!("futex condvar ok");
+    println!("futextest pass.");
+    0
+}
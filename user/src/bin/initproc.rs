@@ -4,7 +4,7 @@
 use core::ptr::null;
 
 use ylib::{
-    exec, fork, println, wait,
+    exec, fork, println, wait, yield_,
     ForkResult::{Child, Parent},
 };
 
@@ -12,8 +12,10 @@ extern crate ylib;
 
 fn recycle() -> ! {
     loop {
-        let (pid, code) = wait();
-        println!("initproc: child {} exited with code {}", pid, code);
+        match wait() {
+            Ok((pid, status)) => println!("initproc: child {} {}", pid, status),
+            Err(_) => yield_(),
+        }
     }
 }
 
@@ -21,6 +23,9 @@ fn recycle() -> ! {
 fn main(_: &[&'static str]) -> i32 {
     match fork() {
         Parent(_) => recycle(),
-        Child => exec("ysh\0", &[null()]),
+        Child => {
+            let errno = exec("ysh\0", &[null()]);
+            panic!("initproc: failed to exec ysh: {}", errno)
+        }
     }
 }
@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::{
+    alarm, getitimer, setitimer, sig_setaction, sig_suspend, syscall::sys_setitimer, time, Errno,
+    ITimer, ITimerVal, Signal, SignalAction, SignalFlags, TimeVal, SIGALRM, SIGPROF, SIGVTALRM,
+};
+
+static ALRM: AtomicUsize = AtomicUsize::new(0);
+static VTALRM: AtomicUsize = AtomicUsize::new(0);
+static PROF: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn on_timer(signal: Signal) {
+    let counter = match signal {
+        SIGALRM => &ALRM,
+        SIGVTALRM => &VTALRM,
+        SIGPROF => &PROF,
+        _ => return,
+    };
+    counter.fetch_add(1, Ordering::SeqCst);
+}
+
+fn every(ms: usize) -> ITimerVal {
+    ITimerVal {
+        interval: TimeVal::from_ms(ms),
+        value: TimeVal::from_ms(ms),
+    }
+}
+
+fn once(ms: usize) -> ITimerVal {
+    ITimerVal {
+        interval: TimeVal::default(),
+        value: TimeVal::from_ms(ms),
+    }
+}
+
+// 在用户态空转直到counter达到n, 最多等5秒
+fn spin_until(counter: &AtomicUsize, n: usize) {
+    let deadline = time() + 5000;
+    while counter.load(Ordering::SeqCst) < n {
+        assert!(time() < deadline, "cpu timer never fired");
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for signal in [SIGALRM, SIGVTALRM, SIGPROF] {
+        sig_setaction(signal, SignalAction::new(on_timer, SignalFlags::empty()));
+    }
+
+    //alarm返回上一个闹钟剩余的秒数
+    assert_eq!(alarm(1), 0);
+    assert_eq!(alarm(5), 1);
+    assert_eq!(alarm(0), 5);
+    assert!(getitimer(ITimer::Real).value.is_zero());
+
+    //ITIMER_REAL按实际时间到期, 进程阻塞时也会触发
+    let start = time();
+    setitimer(ITimer::Real, once(50)).unwrap();
+    assert_eq!(sig_suspend(SignalFlags::empty()), Errno::EINTR);
+    assert!(time() - start >= 50);
+    assert_eq!(ALRM.swap(0, Ordering::SeqCst), 1);
+    assert!(getitimer(ITimer::Real).value.is_zero());
+
+    //周期的ITIMER_VIRTUAL只在用户态运行时计时
+    setitimer(ITimer::Virtual, every(20)).unwrap();
+    spin_until(&VTALRM, 3);
+    let old = setitimer(ITimer::Virtual, ITimerVal::default()).unwrap();
+    assert_eq!(old.interval.as_ms(), 20);
+
+    //ITIMER_PROF同时计入内核态时间
+    setitimer(ITimer::Prof, once(20)).unwrap();
+    spin_until(&PROF, 1);
+    assert!(getitimer(ITimer::Prof).value.is_zero());
+
+    let bad = ITimerVal {
+        interval: TimeVal::default(),
+        value: TimeVal {
+            sec: 0,
+            usec: 1_000_000,
+        },
+    };
+    assert_eq!(setitimer(ITimer::Real, bad).err(), Some(Errno::EINVAL));
+    let zero = ITimerVal::default();
+    assert_eq!(
+        Errno::check(sys_setitimer(3, &zero as *const _ as usize, 0)),
+        Err(Errno::EINVAL)
+    );
+    println!("itimertest pass.");
+    0
+}
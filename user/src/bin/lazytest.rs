@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{exit, fork, sbrk, waitpid, ExitStatus, ForkResult, SIGSEGV};
+
+const PAGE_SIZE: usize = 4096;
+// 远超过物理内存中能立即分配的大小, 只有被访问的页才会分配物理页
+const HEAP_PAGES: usize = 64 * 1024;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let base = sbrk((HEAP_PAGES * PAGE_SIZE) as isize).unwrap();
+    for i in (0..HEAP_PAGES).step_by(1024) {
+        let ptr = (base + i * PAGE_SIZE) as *mut usize;
+        unsafe {
+            assert_eq!(ptr.read_volatile(), 0);
+            ptr.write_volatile(i);
+            assert_eq!(ptr.read_volatile(), i);
+        }
+    }
+    println!("lazy heap ok");
+
+    //堆顶之外的访问仍然是非法的
+    let end = base + HEAP_PAGES * PAGE_SIZE;
+    match fork() {
+        ForkResult::Child => {
+            unsafe { (end as *mut usize).write_volatile(1) };
+            exit(0)
+        }
+        ForkResult::Parent(pid) => {
+            let (_, exit_code) = waitpid(pid).unwrap();
+            assert_eq!(exit_code, ExitStatus::Signaled(SIGSEGV));
+        }
+    }
+    println!("lazytest pass.");
+    0
+}
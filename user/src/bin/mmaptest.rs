@@ -0,0 +1,113 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{
+    exit, fclose, fopen, fork, fread, fwrite, mmap, mmap_anonymous, munmap, waitpid, ExitStatus,
+    ForkResult, MmapFlags, OpenFlags, ProtFlags, SIGSEGV,
+};
+
+const PAGE_SIZE: usize = 4096;
+const LEN: usize = 4 * PAGE_SIZE;
+
+fn touch(base: usize, val: usize) {
+    for i in 0..LEN / PAGE_SIZE {
+        unsafe { ((base + i * PAGE_SIZE) as *mut usize).write_volatile(val + i) };
+    }
+}
+
+fn check(base: usize, val: usize) {
+    for i in 0..LEN / PAGE_SIZE {
+        assert_eq!(
+            unsafe { ((base + i * PAGE_SIZE) as *const usize).read_volatile() },
+            val + i
+        );
+    }
+}
+
+fn run_child(f: impl FnOnce()) -> ExitStatus {
+    match fork() {
+        ForkResult::Child => {
+            f();
+            exit(0)
+        }
+        ForkResult::Parent(pid) => waitpid(pid).unwrap().1,
+    }
+}
+
+fn anonymous() {
+    let rw = ProtFlags::READ | ProtFlags::WRITE;
+    let private = mmap_anonymous(LEN, rw, MmapFlags::PRIVATE).unwrap();
+    let shared = mmap_anonymous(LEN, rw, MmapFlags::SHARED).unwrap();
+    check(private, 0);
+    touch(private, 1);
+    //子进程对私有映射的修改父进程看不到, 对共享映射的修改父进程能看到
+    let exit_code = run_child(|| {
+        check(private, 1);
+        touch(private, 100);
+        touch(shared, 200);
+    });
+    assert_eq!(exit_code, ExitStatus::Exited(0));
+    check(private, 1);
+    check(shared, 200);
+    munmap(private, LEN).unwrap();
+    munmap(shared, LEN).unwrap();
+
+    //解除映射后访问会收到SIGSEGV
+    assert_eq!(
+        run_child(|| check(private, 1)),
+        ExitStatus::Signaled(SIGSEGV)
+    );
+    println!("anonymous mmap ok");
+}
+
+fn file_backed() {
+    let path = "mmapfile\0".as_ptr();
+    let content = [b'a'; PAGE_SIZE + 16];
+    let fd = fopen(
+        path,
+        OpenFlags::CREATE | OpenFlags::WRITE | OpenFlags::TRUNC,
+    )
+    .unwrap();
+    fwrite(fd, &content).unwrap();
+    fclose(fd).unwrap();
+
+    let fd = fopen(path, OpenFlags::READ | OpenFlags::WRITE).unwrap();
+    let rw = ProtFlags::READ | ProtFlags::WRITE;
+    let private = mmap(2 * PAGE_SIZE, rw, MmapFlags::PRIVATE, fd, 0).unwrap();
+    let shared = mmap(2 * PAGE_SIZE, rw, MmapFlags::SHARED, fd, 0).unwrap();
+    let private = unsafe { core::slice::from_raw_parts_mut(private as *mut u8, 2 * PAGE_SIZE) };
+    let shared = unsafe { core::slice::from_raw_parts_mut(shared as *mut u8, 2 * PAGE_SIZE) };
+    //文件末尾之后的部分是0
+    assert_eq!(&private[..content.len()], &content[..]);
+    assert!(private[content.len()..].iter().all(|&b| b == 0));
+    private[0] = b'p';
+    //共享映射的修改在子进程退出时写回文件
+    assert_eq!(
+        run_child(|| shared[PAGE_SIZE] = b's'),
+        ExitStatus::Exited(0)
+    );
+    assert_eq!(shared[PAGE_SIZE], b's');
+    munmap(private.as_ptr() as usize, 2 * PAGE_SIZE).unwrap();
+    munmap(shared.as_ptr() as usize, 2 * PAGE_SIZE).unwrap();
+    fclose(fd).unwrap();
+
+    let fd = fopen(path, OpenFlags::READ).unwrap();
+    let mut buf = [0u8; 2 * PAGE_SIZE];
+    //文件长度不会因为映射而改变
+    assert_eq!(fread(fd, &mut buf).unwrap(), content.len());
+    fclose(fd).unwrap();
+    assert_eq!(buf[0], b'a');
+    assert_eq!(buf[PAGE_SIZE], b's');
+    println!("file-backed mmap ok");
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    anonymous();
+    file_backed();
+    println!("mmaptest pass.");
+    0
+}
@@ -0,0 +1,114 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{
+    exit, fclose, fopen, fork, fwrite, mmap, mmap_anonymous, mprotect, munmap, waitpid, Errno,
+    ExitStatus, ForkResult, MmapFlags, OpenFlags, ProtFlags, SIGSEGV,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+fn write(addr: usize, val: usize) {
+    unsafe { (addr as *mut usize).write_volatile(val) }
+}
+
+fn read(addr: usize) -> usize {
+    unsafe { (addr as *const usize).read_volatile() }
+}
+
+fn run_child(f: impl FnOnce()) -> ExitStatus {
+    match fork() {
+        ForkResult::Child => {
+            f();
+            exit(0)
+        }
+        ForkResult::Parent(pid) => waitpid(pid).unwrap().1,
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let rw = ProtFlags::READ | ProtFlags::WRITE;
+    let base = mmap_anonymous(3 * PAGE_SIZE, rw, MmapFlags::PRIVATE).unwrap();
+    for i in 0..3 {
+        write(base + i * PAGE_SIZE, i);
+    }
+
+    //只把中间一页改成只读, vma会被切成三段
+    let middle = base + PAGE_SIZE;
+    mprotect(middle, PAGE_SIZE, ProtFlags::READ).unwrap();
+    assert_eq!(read(middle), 1);
+    assert_eq!(run_child(|| write(base, 10)), ExitStatus::Exited(0));
+    assert_eq!(
+        run_child(|| write(middle + PAGE_SIZE, 12)),
+        ExitStatus::Exited(0)
+    );
+    assert_eq!(
+        run_child(|| write(middle, 11)),
+        ExitStatus::Signaled(SIGSEGV)
+    );
+    assert_eq!(read(middle), 1);
+
+    //恢复写权限后写时复制仍然正确, 父进程看不到子进程的修改
+    mprotect(middle, PAGE_SIZE, rw).unwrap();
+    assert_eq!(
+        run_child(|| {
+            write(middle, 21);
+            assert_eq!(read(middle), 21);
+        }),
+        ExitStatus::Exited(0)
+    );
+    assert_eq!(read(middle), 1);
+    write(middle, 31);
+    assert_eq!(read(middle), 31);
+    println!("mprotect split ok");
+
+    //PROT_NONE的页不可访问, 但内容还在, fork出的子进程也一样
+    mprotect(middle, PAGE_SIZE, ProtFlags::empty()).unwrap();
+    assert_eq!(
+        run_child(|| {
+            read(middle);
+        }),
+        ExitStatus::Signaled(SIGSEGV)
+    );
+    assert_eq!(
+        run_child(|| write(middle, 41)),
+        ExitStatus::Signaled(SIGSEGV)
+    );
+    mprotect(middle, PAGE_SIZE, ProtFlags::READ).unwrap();
+    assert_eq!(read(middle), 31);
+    mprotect(middle, PAGE_SIZE, rw).unwrap();
+    println!("mprotect none ok");
+
+    //只读打开的文件的共享映射不能加上写权限, 私有映射可以
+    let path = "mprotectfile\0".as_ptr();
+    let fd = fopen(
+        path,
+        OpenFlags::CREATE | OpenFlags::WRITE | OpenFlags::TRUNC,
+    )
+    .unwrap();
+    fwrite(fd, &[b'a'; PAGE_SIZE]).unwrap();
+    fclose(fd).unwrap();
+    let fd = fopen(path, OpenFlags::READ).unwrap();
+    let shared = mmap(PAGE_SIZE, ProtFlags::READ, MmapFlags::SHARED, fd, 0).unwrap();
+    let private = mmap(PAGE_SIZE, ProtFlags::READ, MmapFlags::PRIVATE, fd, 0).unwrap();
+    assert_eq!(mprotect(shared, PAGE_SIZE, rw), Err(Errno::EACCES));
+    mprotect(private, PAGE_SIZE, rw).unwrap();
+    munmap(shared, PAGE_SIZE).unwrap();
+    munmap(private, PAGE_SIZE).unwrap();
+    fclose(fd).unwrap();
+    println!("mprotect file ok");
+
+    assert_eq!(
+        mprotect(base, PAGE_SIZE, ProtFlags::WRITE | ProtFlags::EXEC),
+        Err(Errno::EACCES)
+    );
+    munmap(middle, PAGE_SIZE).unwrap();
+    assert_eq!(mprotect(base, 3 * PAGE_SIZE, rw), Err(Errno::ENOMEM));
+    munmap(base, 3 * PAGE_SIZE).unwrap();
+    println!("mprotecttest pass.");
+    0
+}
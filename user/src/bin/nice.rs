@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use ylib::{exec, getpriority, println, setpriority, types::Argv};
+
+const DEFAULT_ADJUSTMENT: isize = 10;
+
+// nice [-n adjustment] command [args...]
+// 不带命令时打印当前的nice值
+#[no_mangle]
+fn main(argv: &Argv) -> i32 {
+    let (adjustment, cmd) = match argv.get(1) {
+        Some(&"-n") => match argv.get(2).and_then(|n| n.parse::<isize>().ok()) {
+            Some(n) => (n, &argv[3..]),
+            None => {
+                println!("nice: invalid adjustment");
+                return -1;
+            }
+        },
+        _ => (DEFAULT_ADJUSTMENT, &argv[1..]),
+    };
+    let nice = getpriority(0).unwrap();
+    if cmd.is_empty() {
+        println!("{}", nice);
+        return 0;
+    }
+    if let Err(errno) = setpriority(0, nice + adjustment) {
+        println!("nice: {}", errno);
+        return -1;
+    }
+    let args: Vec<String> = cmd.iter().map(|arg| format!("{}\0", arg)).collect();
+    let mut argv: Vec<*const u8> = args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(core::ptr::null());
+    let errno = exec(&args[0], &argv);
+    println!("nice: {}: {}", cmd[0], errno);
+    -1
+}
@@ -0,0 +1,122 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::ptr::null;
+use ylib::{exec, exit, fclose, fopen, fork, fwrite, waitpid, ExitStatus, ForkResult, OpenFlags};
+
+// 内核把位置无关的可执行文件加载到这里
+const PIE_BASE: usize = 0x4000_0000;
+
+// 下面都是加载前的虚拟地址, 和在文件中的偏移相同
+// 代码段在第0页, 数据段在第1页, 动态段也放在数据段里
+const CODE: usize = 0x100;
+const DATA: usize = 0x1000;
+// 要重定位成PIE_BASE + MAGIC的指针
+const PTR: usize = DATA;
+const MAGIC: usize = DATA + 8;
+const RELA: usize = DATA + 16;
+const DYNAMIC: usize = RELA + 24;
+const END: usize = DYNAMIC + 4 * 16;
+
+const EXIT_CODE: u64 = 42;
+
+// 通过重定位后的指针读出MAGIC处的值作为退出码
+// 指针不等于PIE_BASE + MAGIC时退出码是1, 没有重定位时会访问0附近的地址收到SIGSEGV
+const TEXT: [u32; 9] = [
+    0x0000_1297, // auipc t0, 1          t0 = CODE + 0x1000
+    0xf002_b303, // ld t1, -256(t0)      t1 = *PTR
+    0x4000_13b7, // lui t2, 0x40001
+    0x0083_8393, // addi t2, t2, 8       t2 = PIE_BASE + MAGIC
+    0x0010_0513, // li a0, 1
+    0x0073_1463, // bne t1, t2, 8
+    0x0003_3503, // ld a0, 0(t1)
+    0x05d0_0893, // li a7, 93            exit
+    0x0000_0073, // ecall
+];
+
+fn put(elf: &mut [u8], offset: usize, bytes: &[u8]) {
+    elf[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn put_u64(elf: &mut [u8], offset: usize, val: usize) {
+    put(elf, offset, &(val as u64).to_le_bytes());
+}
+
+// 程序头, 地址和文件偏移相同
+fn put_phdr(elf: &mut [u8], idx: usize, ty: u32, flags: u32, start: usize, end: usize) {
+    let ph = 64 + idx * 56;
+    put(elf, ph, &ty.to_le_bytes());
+    put(elf, ph + 4, &flags.to_le_bytes());
+    put_u64(elf, ph + 8, start);
+    put_u64(elf, ph + 16, start);
+    put_u64(elf, ph + 24, start);
+    put_u64(elf, ph + 32, end - start);
+    put_u64(elf, ph + 40, end - start);
+    put_u64(elf, ph + 48, if ty == 1 { 0x1000 } else { 8 });
+}
+
+// 手工构造一个静态链接的PIE, 只有一个R_RISCV_RELATIVE重定位
+fn build_pie() -> Vec<u8> {
+    let mut elf = vec![0u8; END];
+    //ELF头: 64位, 小端, ET_DYN, RISC-V
+    put(&mut elf, 0, &[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+    put(&mut elf, 16, &3u16.to_le_bytes());
+    put(&mut elf, 18, &243u16.to_le_bytes());
+    put(&mut elf, 20, &1u32.to_le_bytes());
+    put_u64(&mut elf, 24, CODE);
+    put_u64(&mut elf, 32, 64);
+    put(&mut elf, 52, &64u16.to_le_bytes());
+    put(&mut elf, 54, &56u16.to_le_bytes());
+    put(&mut elf, 56, &3u16.to_le_bytes());
+    put(&mut elf, 58, &64u16.to_le_bytes());
+    //PT_LOAD(R|X), PT_LOAD(R|W), PT_DYNAMIC
+    put_phdr(&mut elf, 0, 1, 5, 0, CODE + TEXT.len() * 4);
+    put_phdr(&mut elf, 1, 1, 6, DATA, END);
+    put_phdr(&mut elf, 2, 2, 6, DYNAMIC, END);
+    for (i, inst) in TEXT.iter().enumerate() {
+        put(&mut elf, CODE + i * 4, &inst.to_le_bytes());
+    }
+    put_u64(&mut elf, MAGIC, EXIT_CODE as usize);
+    //r_offset, r_info = R_RISCV_RELATIVE, r_addend
+    put_u64(&mut elf, RELA, PTR);
+    put_u64(&mut elf, RELA + 8, 3);
+    put_u64(&mut elf, RELA + 16, MAGIC);
+    //DT_RELA, DT_RELASZ, DT_RELAENT, DT_NULL
+    let dynamic = [(7, RELA), (8, 24), (9, 24), (0, 0)];
+    for (i, &(tag, val)) in dynamic.iter().enumerate() {
+        put_u64(&mut elf, DYNAMIC + i * 16, tag);
+        put_u64(&mut elf, DYNAMIC + i * 16 + 8, val);
+    }
+    elf
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let path = "pie_prog\0";
+    let fd = fopen(
+        path.as_ptr(),
+        OpenFlags::CREATE | OpenFlags::WRITE | OpenFlags::TRUNC,
+    )
+    .unwrap();
+    fwrite(fd, &build_pie()).unwrap();
+    fclose(fd).unwrap();
+
+    let pid = match fork() {
+        ForkResult::Child => {
+            exec(path, &[path.as_ptr(), null()]);
+            exit(-1)
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(
+        waitpid(pid).unwrap(),
+        (pid, ExitStatus::Exited(EXIT_CODE as i32))
+    );
+    println!("pietest pass.");
+    0
+}
@@ -48,7 +48,7 @@ pub fn main() -> i32 {
                 str::parse::<usize>(core::str::from_utf8(&child_result[..result_len]).unwrap())
                     .unwrap()
             );
-            wait();
+            wait().unwrap();
             println!("pipe_large_test passed!");
             0
         }
@@ -0,0 +1,13 @@
+#![no_std]
+#![no_main]
+
+use ylib::types::Argv;
+use ylib::{reboot, RebootType};
+
+#[no_mangle]
+fn main(argv: &Argv) -> i32 {
+    match argv.get(1) {
+        Some(&"-w") | Some(&"--warm") => reboot(RebootType::Warm),
+        _ => reboot(RebootType::Cold),
+    }
+}
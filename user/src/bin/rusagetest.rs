@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{
+    exit, fork, getrusage, mmap_anonymous, times, waitpid, yield_, ExitStatus, ForkResult,
+    MmapFlags, ProtFlags, RUsageWho,
+};
+
+// 只在用户态空转, 不进入内核
+fn spin(iters: usize) {
+    let mut count = 0usize;
+    for _ in 0..iters {
+        unsafe { (&mut count as *mut usize).write_volatile(count.wrapping_add(1)) };
+    }
+}
+
+const SPIN: usize = 10_000_000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let before = getrusage(RUsageWho::Current).unwrap();
+    spin(SPIN);
+    for _ in 0..10 {
+        yield_();
+    }
+    let after = getrusage(RUsageWho::Current).unwrap();
+    assert!(after.utime.as_ms() > before.utime.as_ms());
+    assert!(after.nvcsw >= before.nvcsw + 10);
+    let (ticks, tms) = times();
+    assert!(ticks > 0);
+    assert!(tms.utime > 0);
+
+    //子进程的时间和缺页次数在wait之后才计入
+    let pid = match fork() {
+        ForkResult::Child => {
+            let len = 16 * 4096;
+            let addr = mmap_anonymous(len, ProtFlags::READ | ProtFlags::WRITE, MmapFlags::PRIVATE)
+                .unwrap();
+            for page in (addr..addr + len).step_by(4096) {
+                unsafe { (page as *mut u8).write_volatile(1) };
+            }
+            spin(SPIN);
+            exit(0);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(getrusage(RUsageWho::Children).unwrap().utime.as_ms(), 0);
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+    let children = getrusage(RUsageWho::Children).unwrap();
+    assert!(children.utime.as_ms() > 0);
+    assert!(children.minflt >= 16);
+    let (_, tms) = times();
+    assert!(tms.cutime > 0);
+    println!("rusagetest pass.");
+    0
+}
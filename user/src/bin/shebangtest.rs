@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::ptr::null;
+use ylib::{
+    exec, fclose, fdup, fopen, fork, fread, fwrite, make_pipe, waitpid, Errno, ExitStatus,
+    ForkResult, OpenFlags, STDOUT,
+};
+
+fn write_file(path: &str, data: &[u8]) {
+    let fd = fopen(
+        path.as_ptr(),
+        OpenFlags::CREATE | OpenFlags::WRITE | OpenFlags::TRUNC,
+    )
+    .unwrap();
+    fwrite(fd, data).unwrap();
+    fclose(fd).unwrap();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    //解释器后面的内容整体作为一个参数, 然后是脚本路径和原来的参数
+    write_file("script_echo\0", b"#!echo  hello  world \nignored\n");
+    let [read_fd, write_fd] = make_pipe().unwrap();
+    let pid = match fork() {
+        ForkResult::Child => {
+            fclose(STDOUT).unwrap();
+            assert_eq!(fdup(write_fd).unwrap(), STDOUT);
+            fclose(read_fd).unwrap();
+            fclose(write_fd).unwrap();
+            let errno = exec(
+                "script_echo\0",
+                &["script_echo\0".as_ptr(), "arg\0".as_ptr(), null()],
+            );
+            panic!("exec script failed: {}", errno);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    fclose(write_fd).unwrap();
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    loop {
+        let read = fread(read_fd, &mut buf[len..]).unwrap();
+        if read == 0 {
+            break;
+        }
+        len += read;
+    }
+    fclose(read_fd).unwrap();
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+    assert_eq!(&buf[..len], b"hello  world script_echo arg\n");
+
+    //解释器嵌套太深
+    write_file("script_loop_a\0", b"#!script_loop_b\n");
+    write_file("script_loop_b\0", b"#!script_loop_a\n");
+    assert_eq!(exec("script_loop_a\0", &[null()]), Errno::ELOOP);
+    //没有解释器, 解释器不存在, 以及太长的#!行
+    write_file("script_empty\0", b"#!  \n");
+    assert_eq!(exec("script_empty\0", &[null()]), Errno::ENOEXEC);
+    write_file("script_missing\0", b"#!no_such_interpreter\n");
+    assert_eq!(exec("script_missing\0", &[null()]), Errno::ENOENT);
+    let mut long = [b'a'; 300];
+    long[..2].copy_from_slice(b"#!");
+    write_file("script_long\0", &long);
+    assert_eq!(exec("script_long\0", &[null()]), Errno::ENOEXEC);
+    println!("shebangtest pass.");
+    0
+}
@@ -0,0 +1,88 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::ptr::null;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::{
+    exec, exit, fork, getpid, kill, sig_getaction, sig_setaction, types::Argv, waitpid, Errno,
+    ExitStatus, ForkResult, Pid, SaFlags, Signal, SignalAction, SignalFlags, SIGCHLD, SIGUSR1,
+    SIGUSR2, SIG_DFL, SIG_IGN,
+};
+
+static CHLD: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn on_chld(_: Signal) {
+    CHLD.fetch_add(1, Ordering::SeqCst);
+}
+
+extern "C" fn on_usr1(_: Signal) {}
+
+fn spawn(f: impl FnOnce() -> i32) -> Pid {
+    match fork() {
+        ForkResult::Child => exit(f()),
+        ForkResult::Parent(pid) => pid,
+    }
+}
+
+// exec之后的子进程: 处理函数恢复默认动作, 被忽略的信号保持忽略
+fn after_exec() -> i32 {
+    assert_eq!(sig_getaction(SIGUSR1).handler(), SIG_DFL);
+    assert_eq!(sig_getaction(SIGUSR2).handler(), SIG_IGN);
+    kill(getpid(), SIGUSR2).unwrap();
+    kill(getpid(), SIGUSR1).unwrap();
+    1
+}
+
+#[no_mangle]
+pub fn main(argv: &Argv) -> i32 {
+    if argv.get(1) == Some(&"exec") {
+        return after_exec();
+    }
+
+    //SIGCHLD默认被忽略, 不会杀死父进程
+    let pid = spawn(|| 3);
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(3)));
+
+    //子进程退出时通知父进程, 被信号杀死和正常退出能区分开
+    sig_setaction(SIGCHLD, SignalAction::new(on_chld, SignalFlags::empty()));
+    let pid = spawn(|| {
+        kill(getpid(), SIGUSR1).unwrap();
+        0
+    });
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Signaled(SIGUSR1)));
+    assert_eq!(CHLD.swap(0, Ordering::SeqCst), 1);
+
+    //SA_NOCLDWAIT: 仍然收到SIGCHLD, 但子进程被直接回收
+    sig_setaction(
+        SIGCHLD,
+        SignalAction::new(on_chld, SignalFlags::empty()).with_flags(SaFlags::NOCLDWAIT),
+    );
+    let pid = spawn(|| 0);
+    assert_eq!(waitpid(pid), Err(Errno::ECHILD));
+    assert_eq!(CHLD.swap(0, Ordering::SeqCst), 1);
+
+    //忽略SIGCHLD同样不留下僵尸进程
+    sig_setaction(SIGCHLD, SignalAction::ignore());
+    let pid = spawn(|| 0);
+    assert_eq!(waitpid(pid), Err(Errno::ECHILD));
+    assert_eq!(CHLD.load(Ordering::SeqCst), 0);
+    sig_setaction(SIGCHLD, SignalAction::default());
+
+    //fork继承处理方式, exec之后只有忽略的信号保留下来
+    sig_setaction(SIGUSR1, SignalAction::new(on_usr1, SignalFlags::empty()));
+    sig_setaction(SIGUSR2, SignalAction::ignore());
+    let pid = spawn(|| {
+        assert_eq!(sig_getaction(SIGUSR1).handler(), on_usr1 as usize);
+        let errno = exec(
+            "sigchldtest\0",
+            &["sigchldtest\0".as_ptr(), "exec\0".as_ptr(), null()],
+        );
+        panic!("exec sigchldtest failed: {}", errno);
+    });
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Signaled(SIGUSR1)));
+    println!("sigchldtest pass.");
+    0
+}
@@ -0,0 +1,99 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use ylib::{
+    exit, fork, getpid, kill, sig_setaction, waitpid, ExitStatus, ForkResult, SigInfo, Signal,
+    SignalAction, SignalFlags, SIGSEGV, SIGUSR1, SIGUSR2,
+};
+
+// 按顺序记录处理函数的进入和退出, 退出记为负数
+static EVENTS: [AtomicI32; 8] = [
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+];
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn record(event: i32) {
+    EVENTS[COUNT.fetch_add(1, Ordering::SeqCst)].store(event, Ordering::SeqCst);
+}
+
+fn take_events() -> [i32; 8] {
+    let mut events = [0; 8];
+    for (i, event) in EVENTS.iter().enumerate() {
+        events[i] = event.swap(0, Ordering::SeqCst);
+    }
+    COUNT.store(0, Ordering::SeqCst);
+    events
+}
+
+extern "C" fn on_usr1(signal: Signal) {
+    record(signal);
+    //SIGUSR2没有被屏蔽时会嵌套执行
+    kill(getpid(), SIGUSR2).unwrap();
+    record(-signal);
+}
+
+extern "C" fn on_usr2(signal: Signal) {
+    record(signal);
+    record(-signal);
+}
+
+const BAD_ADDR: usize = 0x8;
+
+extern "C" fn on_segv(signal: Signal, info: &SigInfo) {
+    //返回后会再次触发同样的异常, 直接退出
+    exit(if signal == SIGSEGV && info.addr == BAD_ADDR {
+        0
+    } else {
+        1
+    });
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    sig_setaction(SIGUSR2, SignalAction::new(on_usr2, SignalFlags::empty()));
+
+    sig_setaction(SIGUSR1, SignalAction::new(on_usr1, SignalFlags::empty()));
+    //处理函数返回后kill的返回值和寄存器都保持不变
+    let canary = [1usize, 2, 3, 4];
+    assert_eq!(kill(getpid(), SIGUSR1), Ok(()));
+    assert_eq!(canary, [1, 2, 3, 4]);
+    assert_eq!(
+        take_events(),
+        [SIGUSR1, SIGUSR2, -SIGUSR2, -SIGUSR1, 0, 0, 0, 0]
+    );
+
+    //处理函数执行期间屏蔽SIGUSR2, 返回之后才处理
+    sig_setaction(SIGUSR1, SignalAction::new(on_usr1, SignalFlags::SIGUSR2));
+    kill(getpid(), SIGUSR1).unwrap();
+    assert_eq!(
+        take_events(),
+        [SIGUSR1, -SIGUSR1, SIGUSR2, -SIGUSR2, 0, 0, 0, 0]
+    );
+
+    //SIGSEGV的处理函数能拿到出错的地址
+    let pid = match fork() {
+        ForkResult::Child => {
+            sig_setaction(
+                SIGSEGV,
+                SignalAction::with_info(on_segv, SignalFlags::empty()),
+            );
+            unsafe { (BAD_ADDR as *mut u8).write_volatile(0) };
+            exit(2);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+    println!("sigframetest pass.");
+    0
+}
@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::syscall::sys_sigprocmask;
+use ylib::{
+    exit, fork, getpid, kill, sig_getmask, sig_pending, sig_procmask, sig_setaction, sig_suspend,
+    sleep, waitpid, Errno, ExitStatus, ForkResult, SigHow, Signal, SignalAction, SignalFlags,
+    SIGUSR1, SIGUSR2,
+};
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn on_signal(_: Signal) {
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn handled() -> usize {
+    HANDLED.swap(0, Ordering::SeqCst)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    sig_setaction(SIGUSR1, SignalAction::new(on_signal, SignalFlags::empty()));
+    sig_setaction(SIGUSR2, SignalAction::new(on_signal, SignalFlags::empty()));
+
+    //被屏蔽的信号保持未决, 不会调用处理函数
+    assert_eq!(
+        sig_procmask(SigHow::Block, SignalFlags::SIGUSR1),
+        Ok(SignalFlags::empty())
+    );
+    assert_eq!(sig_getmask(), SignalFlags::SIGUSR1);
+    kill(getpid(), SIGUSR1).unwrap();
+    assert_eq!(handled(), 0);
+    assert_eq!(sig_pending(), SignalFlags::SIGUSR1);
+
+    //SIGKILL不能被屏蔽, 非法的how不修改屏蔽字
+    sig_procmask(SigHow::Block, SignalFlags::SIGKILL).unwrap();
+    assert_eq!(sig_getmask(), SignalFlags::SIGUSR1);
+    let set = 0u64;
+    assert_eq!(
+        Errno::check(sys_sigprocmask(3, &set as *const _ as usize, 0)),
+        Err(Errno::EINVAL)
+    );
+    assert_eq!(sig_getmask(), SignalFlags::SIGUSR1);
+
+    //解除屏蔽后马上处理
+    assert_eq!(
+        sig_procmask(SigHow::Unblock, SignalFlags::SIGUSR1),
+        Ok(SignalFlags::SIGUSR1)
+    );
+    assert_eq!(handled(), 1);
+    assert_eq!(sig_pending(), SignalFlags::empty());
+
+    //sigsuspend在等待期间放开SIGUSR2, 返回后恢复原来的屏蔽字
+    sig_procmask(SigHow::SetMask, SignalFlags::SIGUSR2).unwrap();
+    let parent = getpid();
+    let pid = match fork() {
+        ForkResult::Child => {
+            //子进程继承屏蔽字
+            assert_eq!(sig_getmask(), SignalFlags::SIGUSR2);
+            sleep(50);
+            kill(parent, SIGUSR2).unwrap();
+            exit(0);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(sig_suspend(SignalFlags::empty()), Errno::EINTR);
+    assert_eq!(handled(), 1);
+    assert_eq!(sig_getmask(), SignalFlags::SIGUSR2);
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Exited(0)));
+    println!("sigmasktest pass.");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::{
+    exit, fclose, fork, fwrite, make_pipe, sig_setaction, waitpid, Errno, ExitStatus, Fd,
+    ForkResult, Signal, SignalAction, SignalFlags, SIGPIPE,
+};
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn on_pipe(_: Signal) {
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+// 返回读端已经关闭的管道的写端
+fn broken_pipe() -> Fd {
+    let [reader, writer] = make_pipe().unwrap();
+    fclose(reader).unwrap();
+    writer
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    //默认动作是终止写者
+    let pid = match fork() {
+        ForkResult::Child => {
+            let _ = fwrite(broken_pipe(), b"lost");
+            exit(1);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    assert_eq!(waitpid(pid).unwrap(), (pid, ExitStatus::Signaled(SIGPIPE)));
+
+    //有处理函数时写入返回EPIPE
+    sig_setaction(SIGPIPE, SignalAction::new(on_pipe, SignalFlags::empty()));
+    let writer = broken_pipe();
+    assert_eq!(fwrite(writer, b"lost"), Err(Errno::EPIPE));
+    assert_eq!(HANDLED.swap(0, Ordering::SeqCst), 1);
+
+    //忽略时只返回EPIPE
+    sig_setaction(SIGPIPE, SignalAction::ignore());
+    assert_eq!(fwrite(writer, b"lost"), Err(Errno::EPIPE));
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 0);
+    fclose(writer).unwrap();
+    println!("sigpipetest pass.");
+    0
+}
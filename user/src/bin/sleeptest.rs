@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use ylib::{exit, fork, sleep, time, wait};
+
+const SLEEPERS: usize = 5;
+const INTERVAL: usize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for i in 1..=SLEEPERS {
+        match fork() {
+            ylib::ForkResult::Parent(_) => {}
+            ylib::ForkResult::Child => {
+                let duration = i * INTERVAL;
+                let start = time();
+                sleep(duration);
+                let elapsed = time() - start;
+                assert!(elapsed >= duration);
+                println!("child {} slept {}ms for {}ms", i, elapsed, duration);
+                exit(0)
+            }
+        }
+    }
+    for _ in 0..SLEEPERS {
+        wait().unwrap();
+    }
+    println!("sleeptest pass.");
+    0
+}
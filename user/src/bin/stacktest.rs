@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+
+use core::ptr::{read_volatile, write_volatile};
+use ylib::{
+    exit, fork, get_stack_limit, mprotect, set_stack_limit, waitpid, Errno, ExitStatus, ForkResult,
+    ProtFlags, RLimit, SIGSEGV,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+// 每层递归大约占用1KiB的栈
+fn recurse(depth: usize) -> usize {
+    let mut buf = [0u8; 1024];
+    unsafe { write_volatile(&mut buf[0], depth as u8) };
+    if depth == 0 {
+        return 0;
+    }
+    recurse(depth - 1) + unsafe { read_volatile(&buf[0]) } as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    //mprotect把当前的栈页切出来, 栈仍然能从最低的一段向下增长
+    let local = 0usize;
+    let page = &local as *const usize as usize & !(PAGE_SIZE - 1);
+    mprotect(page, PAGE_SIZE, ProtFlags::READ | ProtFlags::WRITE).unwrap();
+    //远超过初始的两页栈
+    recurse(256);
+    println!("stack grows on demand");
+
+    //超过rlimit后会收到SIGSEGV
+    match fork() {
+        ForkResult::Child => {
+            let limit = get_stack_limit().unwrap();
+            set_stack_limit(RLimit {
+                cur: 64 * 1024,
+                max: limit.max,
+            })
+            .unwrap();
+            recurse(usize::MAX);
+            exit(0)
+        }
+        ForkResult::Parent(pid) => {
+            let (_, exit_code) = waitpid(pid).unwrap();
+            assert_eq!(exit_code, ExitStatus::Signaled(SIGSEGV));
+        }
+    }
+    println!("stack overflow is caught");
+
+    let limit = get_stack_limit().unwrap();
+    assert_eq!(
+        set_stack_limit(RLimit {
+            cur: limit.max + 1,
+            max: limit.max,
+        }),
+        Err(Errno::EINVAL)
+    );
+    assert_eq!(
+        set_stack_limit(RLimit {
+            cur: limit.cur,
+            max: limit.max + 4096,
+        }),
+        Err(Errno::EPERM)
+    );
+    println!("stacktest pass.");
+    0
+}
@@ -0,0 +1,112 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::{
+    sync::{enable_deadlock_detect, Condvar, Mutex, Semaphore},
+    thread_create, waittid, yield_, Errno,
+};
+
+const THREADS: usize = 4;
+const ROUNDS: usize = 200;
+
+static mut COUNTER: usize = 0;
+static READY: AtomicUsize = AtomicUsize::new(0);
+
+fn mutex_test(mutex: Mutex) {
+    let tids: Vec<_> = (0..THREADS)
+        .map(|_| {
+            thread_create(move || {
+                for _ in 0..ROUNDS {
+                    mutex.lock().unwrap();
+                    //故意拉长临界区, 没有锁保护时结果一定不对
+                    let old = unsafe { COUNTER };
+                    yield_();
+                    unsafe { COUNTER = old + 1 };
+                    mutex.unlock().unwrap();
+                }
+                0
+            })
+            .unwrap()
+        })
+        .collect();
+    for tid in tids {
+        waittid(tid).unwrap();
+    }
+    assert_eq!(unsafe { COUNTER }, THREADS * ROUNDS);
+    unsafe { COUNTER = 0 };
+}
+
+fn semaphore_test() {
+    let items = Semaphore::new(0).unwrap();
+    let producer = thread_create(move || {
+        for _ in 0..ROUNDS {
+            items.up().unwrap();
+        }
+        0
+    })
+    .unwrap();
+    for _ in 0..ROUNDS {
+        items.down().unwrap();
+    }
+    waittid(producer).unwrap();
+}
+
+fn condvar_test() {
+    let mutex = Mutex::new().unwrap();
+    let condvar = Condvar::new().unwrap();
+    let waiter = thread_create(move || {
+        mutex.lock().unwrap();
+        while READY.load(Ordering::Relaxed) == 0 {
+            condvar.wait(&mutex).unwrap();
+        }
+        mutex.unlock().unwrap();
+        0
+    })
+    .unwrap();
+    mutex.lock().unwrap();
+    READY.store(1, Ordering::Relaxed);
+    condvar.signal().unwrap();
+    mutex.unlock().unwrap();
+    waittid(waiter).unwrap();
+}
+
+fn deadlock_test() {
+    enable_deadlock_detect(true).unwrap();
+    let mutex = Mutex::new().unwrap();
+    mutex.lock().unwrap();
+    assert_eq!(mutex.lock(), Err(Errno::EDEADLK));
+    //别的线程不能释放不属于它的锁, 也不会破坏死锁检测的状态
+    let other = thread_create(move || {
+        assert_eq!(mutex.unlock(), Err(Errno::EPERM));
+        0
+    })
+    .unwrap();
+    waittid(other).unwrap();
+    mutex.unlock().unwrap();
+    mutex.lock().unwrap();
+    mutex.unlock().unwrap();
+    assert_eq!(mutex.unlock(), Err(Errno::EPERM));
+    enable_deadlock_detect(false).unwrap();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    mutex_test(Mutex::new_spin().unwrap());
+    println!("spin mutex ok");
+    mutex_test(Mutex::new().unwrap());
+    println!("blocking mutex ok");
+    semaphore_test();
+    println!("semaphore ok");
+    condvar_test();
+    println!("condvar ok");
+    deadlock_test();
+    println!("deadlock detect ok");
+    println!("synctest pass.");
+    0
+}
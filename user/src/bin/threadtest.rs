@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate ylib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ylib::{exec, gettid, thread_create, waittid, yield_, Errno};
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 1000;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(gettid(), 0);
+    assert_eq!(waittid(gettid()), Err(Errno::EDEADLK));
+
+    let tids: Vec<_> = (0..THREADS)
+        .map(|i| {
+            thread_create(move || {
+                for _ in 0..ROUNDS {
+                    COUNTER.fetch_add(1, Ordering::Relaxed);
+                    yield_();
+                }
+                println!("thread {} done", gettid());
+                i as i32
+            })
+            .unwrap()
+        })
+        .collect();
+    //还有其他线程在运行时不允许exec
+    assert_eq!(exec("threadtest\0", &[core::ptr::null()]), Errno::EBUSY);
+
+    for (i, &tid) in tids.iter().enumerate() {
+        assert_eq!(waittid(tid), Ok(i as i32));
+    }
+    assert_eq!(waittid(tids[0]), Err(Errno::ESRCH));
+    assert_eq!(COUNTER.load(Ordering::Relaxed), THREADS * ROUNDS);
+    println!("threadtest pass.");
+    0
+}
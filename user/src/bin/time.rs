@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::ptr::null;
+use ylib::{
+    exec, exit, fork, getrusage, println, time, types::Argv, waitpid, ForkResult, RUsageWho,
+};
+
+// 用法: time 命令 [参数...], 运行结束后打印实际时间, 用户态时间和内核态时间
+#[no_mangle]
+fn main(argv: &Argv) -> i32 {
+    if argv.len() < 2 {
+        println!("usage: time command [args...]");
+        return -1;
+    }
+    let args: Vec<String> = argv[1..]
+        .iter()
+        .map(|arg| {
+            let mut arg = String::from(*arg);
+            arg.push('\0');
+            arg
+        })
+        .collect();
+    let start = time();
+    let pid = match fork() {
+        ForkResult::Child => {
+            let mut ptrs: Vec<*const u8> = args.iter().map(|arg| arg.as_ptr()).collect();
+            ptrs.push(null());
+            let errno = exec(&args[0], &ptrs);
+            println!("time: {}: {}", argv[1], errno);
+            exit(-1);
+        }
+        ForkResult::Parent(pid) => pid,
+    };
+    let (_, status) = waitpid(pid).unwrap();
+    let real = time() - start;
+    let usage = getrusage(RUsageWho::Children).unwrap();
+    println!(
+        "real {}ms, user {}ms, sys {}ms",
+        real,
+        usage.utime.as_ms(),
+        usage.stime.as_ms()
+    );
+    println!(
+        "page faults {}, voluntary switches {}, involuntary switches {}",
+        usage.minflt, usage.nvcsw, usage.nivcsw
+    );
+    status.code()
+}
@@ -19,7 +19,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use ylib::{
     console::{getchar, STDIN, STDOUT},
-    exec, exit, fclose, fdup, fopen, fork, make_pipe,
+    env, exec, exit, fclose, fdup, fopen, fork, getenv, make_pipe, setenv,
     types::CStr,
     wait,
     ForkResult::Child,
@@ -119,8 +119,8 @@ impl CommandChain {
                 if let Some(input) = input {
                     let fd = match fopen(input.as_ptr() as *const _, OpenFlags::READ) {
                         Ok(ok) => ok,
-                        Err(_) => {
-                            println!("ysh: failed to open file");
+                        Err(errno) => {
+                            println!("ysh: failed to open file: {}", errno);
                             exit(-1);
                         }
                     };
@@ -147,8 +147,8 @@ impl CommandChain {
                         OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNC,
                     ) {
                         Ok(ok) => ok,
-                        Err(_) => {
-                            println!("ysh: failed to open file");
+                        Err(errno) => {
+                            println!("ysh: failed to open file: {}", errno);
                             exit(-1);
                         }
                     };
@@ -210,7 +210,9 @@ impl CommandChain {
 
                 let argv = &Cmd::argv(&args);
                 let cmd = unsafe { args.get_unchecked(0) };
-                exec(cmd, argv)
+                let errno = exec(cmd, argv);
+                println!("ysh: {}: {}", cmd.trim_end_matches('\0'), errno);
+                exit(-1);
             }
         }
 
@@ -224,8 +226,10 @@ impl CommandChain {
         }
 
         for _ in 0..len {
-            let (pid, code) = wait();
-            println!("ysh: child {} exited with code {}", pid, code);
+            match wait() {
+                Ok((pid, status)) => println!("ysh: child {} {}", pid, status),
+                Err(_) => return Err("ysh: failed to wait for child"),
+            }
         }
 
         Ok(())
@@ -249,6 +253,33 @@ impl CommandChain {
     }
 }
 
+// 在ysh进程中执行的内建命令, 不是内建命令时返回false
+fn run_builtin(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        // export KEY=VALUE设置环境变量, 之后启动的命令都能看到; 不带参数时列出所有环境变量
+        Some("export") => {
+            let mut vars = words.peekable();
+            if vars.peek().is_none() {
+                for (key, value) in env() {
+                    println!("export {}={}", key, value);
+                }
+            }
+            for var in vars {
+                let (key, value) = match var.split_once('=') {
+                    Some((key, value)) => (key, String::from(value)),
+                    None => (var, getenv(var).unwrap_or_default()),
+                };
+                if setenv(key, &value).is_err() {
+                    println!("ysh: export: `{}`: not a valid identifier", var);
+                }
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
 #[no_mangle]
 pub fn main() -> i32 {
     print!("{}", WELCOME);
@@ -257,9 +288,11 @@ pub fn main() -> i32 {
         match getchar() {
             LF | CR => {
                 println!("");
-                if let Some(commands) = CommandChain::new(&line) {
-                    if let Err(msg) = commands.exec() {
-                        println!("{}", msg);
+                if !run_builtin(&line) {
+                    if let Some(commands) = CommandChain::new(&line) {
+                        if let Err(msg) = commands.exec() {
+                            println!("{}", msg);
+                        }
                     }
                 }
                 line.clear();
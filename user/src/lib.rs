@@ -11,23 +11,16 @@ pub mod syscall;
 pub mod libs;
 
 use alloc::vec::Vec;
-use core::{slice, str};
 pub use libs::*;
 
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub unsafe extern "C" fn _start(argc: usize, argv_base: *const CStr) -> ! {
+pub unsafe extern "C" fn _start(argc: usize, argv_base: *const CStr, envp: *const CStr) -> ! {
     heap_alloc::init();
-    let mut argv: Vec<&'static str> = Vec::with_capacity(argc);
-    for i in 0..argc {
-        let arg_base = (argv_base.add(i)).read_volatile();
-        let arg_end = (arg_base as usize..)
-            .find(|&x| (x as *const u8).read_volatile() == 0)
-            .unwrap_unchecked();
-        argv.push(str::from_utf8_unchecked(slice::from_ptr_range(
-            arg_base..arg_end as *const u8,
-        )));
-    }
+    env::init(envp);
+    let argv: Vec<&'static str> = (0..argc)
+        .map(|i| env::cstr_to_str(argv_base.add(i).read_volatile()))
+        .collect();
 
     exit(main(&argv));
 }
@@ -0,0 +1,131 @@
+// 环境变量和辅助向量(auxv)
+// exec时内核把它们放在新程序的初始栈上, _start复制一份到这里
+// 之后的修改只影响当前进程, fork出的子进程会继承, exec时作为envp传给新程序
+
+use alloc::{string::String, vec::Vec};
+use core::{ptr, slice, str};
+
+use super::errno::Errno;
+use super::futex::Mutex;
+use super::types::{CStr, Result};
+
+// auxv的类型, 数值与linux相同
+pub const AT_NULL: usize = 0;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_ENTRY: usize = 9;
+pub const AT_RANDOM: usize = 25;
+
+// 每一项都是"KEY=VALUE\0", 可以直接作为envp传给exec
+static ENVIRON: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static AUXV: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+// 读取内核放在栈上的以0结尾的字符串, 不包括结尾的0
+pub(crate) unsafe fn cstr_to_str(ptr: CStr) -> &'static str {
+    let end = (ptr as usize..)
+        .find(|&x| (x as *const u8).read_volatile() == 0)
+        .unwrap_unchecked();
+    str::from_utf8_unchecked(slice::from_ptr_range(ptr..end as *const u8))
+}
+
+// envp以空指针结尾, 后面紧跟着以AT_NULL结尾的auxv
+pub(crate) unsafe fn init(mut envp: *const CStr) {
+    if envp.is_null() {
+        return;
+    }
+    let mut environ = ENVIRON.lock();
+    while !envp.read_volatile().is_null() {
+        let mut var = String::from(cstr_to_str(envp.read_volatile()));
+        var.push('\0');
+        environ.push(var);
+        envp = envp.add(1);
+    }
+    let mut auxv = envp.add(1) as *const [usize; 2];
+    let mut auxv_list = AUXV.lock();
+    loop {
+        let [ty, val] = auxv.read_volatile();
+        if ty == AT_NULL {
+            break;
+        }
+        auxv_list.push((ty, val));
+        auxv = auxv.add(1);
+    }
+}
+
+fn split(var: &str) -> (&str, &str) {
+    let var = var.trim_end_matches('\0');
+    var.split_once('=').unwrap_or((var, ""))
+}
+
+fn position(environ: &[String], key: &str) -> Option<usize> {
+    environ.iter().position(|var| split(var).0 == key)
+}
+
+fn check_key(key: &str) -> Result {
+    if key.is_empty() || key.contains('=') || key.contains('\0') {
+        Err(Errno::EINVAL)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn getenv(key: &str) -> Option<String> {
+    let environ = ENVIRON.lock();
+    position(&environ, key).map(|idx| String::from(split(&environ[idx]).1))
+}
+
+// 已经存在时覆盖原来的值, 变量名为空或者包含'='时返回EINVAL
+pub fn setenv(key: &str, value: &str) -> Result {
+    check_key(key)?;
+    if value.contains('\0') {
+        return Err(Errno::EINVAL);
+    }
+    let mut var = String::with_capacity(key.len() + value.len() + 2);
+    var.push_str(key);
+    var.push('=');
+    var.push_str(value);
+    var.push('\0');
+    let mut environ = ENVIRON.lock();
+    match position(&environ, key) {
+        Some(idx) => environ[idx] = var,
+        None => environ.push(var),
+    }
+    Ok(())
+}
+
+// 变量不存在时什么也不做
+pub fn unsetenv(key: &str) -> Result {
+    check_key(key)?;
+    let mut environ = ENVIRON.lock();
+    if let Some(idx) = position(&environ, key) {
+        environ.remove(idx);
+    }
+    Ok(())
+}
+
+// 所有环境变量的(名字, 值)
+pub fn env() -> Vec<(String, String)> {
+    ENVIRON
+        .lock()
+        .iter()
+        .map(|var| {
+            let (key, value) = split(var);
+            (String::from(key), String::from(value))
+        })
+        .collect()
+}
+
+// 查询内核在exec时提供的auxv, 例如getauxval(AT_PAGESZ)
+pub fn getauxval(ty: usize) -> Option<usize> {
+    AUXV.lock()
+        .iter()
+        .find(|&&(t, _)| t == ty)
+        .map(|&(_, val)| val)
+}
+
+// 以当前的环境变量构造以空指针结尾的envp, 在f返回前有效
+pub(crate) fn with_envp<R>(f: impl FnOnce(&[CStr]) -> R) -> R {
+    let environ = ENVIRON.lock();
+    let mut envp: Vec<CStr> = environ.iter().map(|var| var.as_ptr()).collect();
+    envp.push(ptr::null());
+    f(&envp)
+}
@@ -0,0 +1,81 @@
+use core::fmt::{self, Display};
+
+macro_rules! errno {
+    ($($name: ident = $code: literal => $desc: literal,)*) => {
+        /// 系统调用的错误码, 数值与linux保持一致
+        #[repr(isize)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Errno {
+            $($name = $code,)*
+        }
+
+        impl Errno {
+            pub fn from_code(code: isize) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+
+            pub fn description(self) -> &'static str {
+                match self {
+                    $(Self::$name => $desc,)*
+                }
+            }
+        }
+    };
+}
+
+errno! {
+    EPERM = 1 => "operation not permitted",
+    ENOENT = 2 => "no such file or directory",
+    ESRCH = 3 => "no such process",
+    EINTR = 4 => "interrupted system call",
+    EIO = 5 => "input/output error",
+    ENXIO = 6 => "no such device or address",
+    E2BIG = 7 => "argument list too long",
+    ENOEXEC = 8 => "exec format error",
+    EBADF = 9 => "bad file descriptor",
+    ECHILD = 10 => "no child processes",
+    EAGAIN = 11 => "resource temporarily unavailable",
+    ENOMEM = 12 => "cannot allocate memory",
+    EACCES = 13 => "permission denied",
+    EFAULT = 14 => "bad address",
+    EBUSY = 16 => "device or resource busy",
+    EEXIST = 17 => "file exists",
+    ENODEV = 19 => "no such device",
+    ENOTDIR = 20 => "not a directory",
+    EISDIR = 21 => "is a directory",
+    EINVAL = 22 => "invalid argument",
+    ENFILE = 23 => "too many open files in system",
+    EMFILE = 24 => "too many open files",
+    ENOTTY = 25 => "inappropriate ioctl for device",
+    EFBIG = 27 => "file too large",
+    ENOSPC = 28 => "no space left on device",
+    ESPIPE = 29 => "illegal seek",
+    EROFS = 30 => "read-only file system",
+    EPIPE = 32 => "broken pipe",
+    ERANGE = 34 => "numerical result out of range",
+    EDEADLK = 35 => "resource deadlock avoided",
+    ENAMETOOLONG = 36 => "file name too long",
+    ENOSYS = 38 => "function not implemented",
+    ELOOP = 40 => "too many levels of symbolic links",
+    ETIMEDOUT = 110 => "connection timed out",
+}
+
+impl Errno {
+    /// 把系统调用的返回值转换为Result, 负的返回值是错误码的相反数
+    pub fn check(ret: isize) -> Result<usize, Errno> {
+        if ret < 0 {
+            Err(Self::from_code(-ret).unwrap_or(Self::EIO))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl Display for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
@@ -0,0 +1,146 @@
+// 基于futex的用户态同步原语, 没有竞争时不会陷入内核
+
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{syscall::sys_futex, Errno, Result, TimeSpec};
+
+const FUTEX_WAIT: usize = 0;
+const FUTEX_WAKE: usize = 1;
+
+// atomic的值等于val时阻塞, 直到被唤醒, 超时或者被信号打断
+// 值不相等时返回EAGAIN, 超时返回ETIMEDOUT
+pub fn futex_wait(atomic: &AtomicU32, val: u32, timeout: Option<&TimeSpec>) -> Result {
+    let timeout = timeout.map_or(ptr::null(), |t| t as *const _);
+    Errno::check(sys_futex(
+        atomic as *const _ as usize,
+        FUTEX_WAIT,
+        val as usize,
+        timeout as usize,
+    ))
+    .map(|_| ())
+}
+
+// 唤醒最多count个阻塞在atomic上的线程, 返回唤醒的个数
+pub fn futex_wake(atomic: &AtomicU32, count: usize) -> usize {
+    sys_futex(atomic as *const _ as usize, FUTEX_WAKE, count, 0).max(0) as usize
+}
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+// 有线程可能阻塞在锁上, 解锁时需要唤醒
+const CONTENDED: u32 = 2;
+
+pub struct Mutex<T> {
+    state: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            self.lock_contended();
+        }
+        MutexGuard { mutex: self }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| MutexGuard { mutex: self })
+    }
+
+    // 拿到锁时不知道是否还有其他等待者, 只能保守地标记为CONTENDED
+    fn lock_contended(&self) {
+        while self.state.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+            let _ = futex_wait(&self.state, CONTENDED, None);
+        }
+    }
+
+    fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            futex_wake(&self.state, 1);
+        }
+    }
+}
+
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+// 每次通知都让序号加一, 等待者据此判断睡眠前是否错过了通知
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    // 释放锁后睡眠, 醒来后重新加锁, 可能虚假唤醒, 调用者需要重新检查条件
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        let _ = futex_wait(&self.seq, seq, None);
+        mutex.lock()
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, 1);
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, u32::MAX as usize);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
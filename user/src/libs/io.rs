@@ -1,15 +1,11 @@
 use crate::syscall::{sys_close, sys_dup, sys_open, sys_pipe, sys_read, sys_seek, sys_write};
 
+use super::errno::Errno;
 use super::types::{CStr, Fd, Result};
 use bitflags::bitflags;
 
 pub fn fdup(fd: usize) -> Result<Fd> {
-    let ret = sys_dup(fd);
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret as Fd)
-    }
+    Errno::check(sys_dup(fd))
 }
 
 bitflags! {
@@ -23,21 +19,11 @@ bitflags! {
 }
 
 pub fn fopen(path: CStr, flags: OpenFlags) -> Result<Fd> {
-    let ret = sys_open(path as usize, flags.bits() as usize);
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret as Fd)
-    }
+    Errno::check(sys_open(path as usize, flags.bits() as usize))
 }
 
 pub fn fclose(fd: Fd) -> Result<()> {
-    let ret = sys_close(fd);
-    if ret != 0 {
-        Err(())
-    } else {
-        Ok(())
-    }
+    Errno::check(sys_close(fd)).map(|_| ())
 }
 
 pub enum SeekType {
@@ -47,38 +33,19 @@ pub enum SeekType {
 }
 
 pub fn fseek(fd: Fd, offset: isize, whence: SeekType) -> Result<usize> {
-    let ret = sys_seek(fd, offset as usize, whence as usize);
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret as usize)
-    }
+    Errno::check(sys_seek(fd, offset as usize, whence as usize))
 }
 
 pub fn fread(fd: Fd, buffer: &mut [u8]) -> Result<usize> {
-    let ret = sys_read(fd, buffer.as_mut_ptr() as usize, buffer.len());
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret as usize)
-    }
+    Errno::check(sys_read(fd, buffer.as_mut_ptr() as usize, buffer.len()))
 }
 
 pub fn fwrite(fd: Fd, buffer: &[u8]) -> Result<usize> {
-    let ret = sys_write(fd, buffer.as_ptr() as usize, buffer.len());
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret as usize)
-    }
+    Errno::check(sys_write(fd, buffer.as_ptr() as usize, buffer.len()))
 }
 
 pub fn make_pipe() -> Result<[Fd; 2]> {
     let mut pipe: [Fd; 2] = [0, 0];
-    let ret = sys_pipe(&mut pipe as *mut _ as usize);
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(pipe)
-    }
+    Errno::check(sys_pipe(&mut pipe as *mut _ as usize))?;
+    Ok(pipe)
 }
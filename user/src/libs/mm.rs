@@ -0,0 +1,82 @@
+use crate::syscall::{sys_getrlimit, sys_mmap, sys_mprotect, sys_munmap, sys_setrlimit};
+
+use super::errno::Errno;
+use super::types::{Fd, RLimit, Result};
+use bitflags::bitflags;
+
+bitflags! {
+    pub struct ProtFlags: usize {
+        const READ = 1 << 0;
+        const WRITE = 1 << 1;
+        const EXEC = 1 << 2;
+    }
+}
+
+bitflags! {
+    pub struct MmapFlags: usize {
+        const SHARED = 1 << 0;
+        const PRIVATE = 1 << 1;
+        const FIXED = 1 << 4;
+        const ANONYMOUS = 1 << 5;
+    }
+}
+
+// 匿名映射, 返回映射的起始地址, 内容全为0
+pub fn mmap_anonymous(len: usize, prot: ProtFlags, flags: MmapFlags) -> Result<usize> {
+    Errno::check(sys_mmap(
+        0,
+        len,
+        prot.bits(),
+        (flags | MmapFlags::ANONYMOUS).bits(),
+        0,
+        0,
+    ))
+}
+
+// 把fd从offset开始的内容映射到内存中, offset要按页对齐
+// 共享映射的修改在munmap或进程退出时写回文件
+pub fn mmap(len: usize, prot: ProtFlags, flags: MmapFlags, fd: Fd, offset: usize) -> Result<usize> {
+    Errno::check(sys_mmap(0, len, prot.bits(), flags.bits(), fd, offset))
+}
+
+// 映射到固定地址addr, 原有的映射会被替换
+pub fn mmap_fixed(
+    addr: usize,
+    len: usize,
+    prot: ProtFlags,
+    flags: MmapFlags,
+    fd: Fd,
+    offset: usize,
+) -> Result<usize> {
+    Errno::check(sys_mmap(
+        addr,
+        len,
+        prot.bits(),
+        (flags | MmapFlags::FIXED).bits(),
+        fd,
+        offset,
+    ))
+}
+
+pub fn munmap(addr: usize, len: usize) -> Result {
+    Errno::check(sys_munmap(addr, len)).map(|_| ())
+}
+
+// addr要按页对齐, 访问权限不符时会收到SIGSEGV
+pub fn mprotect(addr: usize, len: usize, prot: ProtFlags) -> Result {
+    Errno::check(sys_mprotect(addr, len, prot.bits())).map(|_| ())
+}
+
+const RLIMIT_STACK: usize = 3;
+
+// 主线程的栈最多能增长到cur字节, 超过后访问栈会收到SIGSEGV
+pub fn get_stack_limit() -> Result<RLimit> {
+    let mut rlim = RLimit::default();
+    Errno::check(sys_getrlimit(RLIMIT_STACK, &mut rlim as *mut _ as usize))?;
+    Ok(rlim)
+}
+
+// 硬限制只能调低
+pub fn set_stack_limit(rlim: RLimit) -> Result {
+    Errno::check(sys_setrlimit(RLIMIT_STACK, &rlim as *const _ as usize)).map(|_| ())
+}
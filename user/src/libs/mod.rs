@@ -1,21 +1,33 @@
 #[macro_use]
 pub mod console;
+pub mod env;
+pub mod errno;
+pub mod futex;
 pub mod io;
+pub mod mm;
 pub mod signal;
+pub mod sync;
+pub mod thread;
 pub mod types;
 use crate::syscall::{
-    sys_exec, sys_exit, sys_fork, sys_getpid, sys_gettime, sys_sbrk, sys_shutdown, sys_waitpid,
-    sys_yield,
+    sys_exec, sys_exit_group, sys_fork, sys_getitimer, sys_getpid, sys_getpriority, sys_getrusage,
+    sys_gettime, sys_nanosleep, sys_reboot, sys_sbrk, sys_setitimer, sys_setpriority, sys_shutdown,
+    sys_times, sys_waitpid, sys_yield,
 };
 
 pub use self::console::*;
+pub use self::env::*;
+pub use self::errno::*;
 pub use self::io::*;
+pub use self::mm::*;
 pub use self::signal::*;
+pub use self::thread::*;
 pub use self::types::*;
 
+// 退出整个进程, 只退出当前线程用thread_exit
 pub fn exit(exit_code: i32) -> ! {
-    sys_exit(exit_code as usize);
-    panic!("unreachable after sys_exit!");
+    sys_exit_group(exit_code as usize);
+    panic!("unreachable after sys_exit_group!");
 }
 
 pub fn yield_() {
@@ -26,13 +38,8 @@ pub fn time() -> Ms {
     sys_gettime() as Ms
 }
 
-pub fn sbrk(size: isize) -> Result<isize> {
-    let ret = sys_sbrk(size as usize);
-    if ret < 0 {
-        Err(())
-    } else {
-        Ok(ret)
-    }
+pub fn sbrk(size: isize) -> Result<usize> {
+    Errno::check(sys_sbrk(size as usize))
 }
 
 pub enum ForkResult {
@@ -49,45 +56,169 @@ pub fn fork() -> ForkResult {
     }
 }
 
-pub fn exec(path: &str, args: &[CStr]) -> ! {
-    sys_exec(path.as_ptr() as usize, args.as_ptr() as usize);
-    panic!("unreachable after sys_exec!");
+// 只有失败时才会返回, 新程序继承当前的环境变量
+pub fn exec(path: &str, args: &[CStr]) -> Errno {
+    env::with_envp(|envp| execve(path, args, envp))
 }
 
-pub fn waitpid(pid: Pid) -> Result<(Pid, ExitCode), ()> {
-    let mut exit_code: i32 = 0;
-    loop {
-        match sys_waitpid(pid, &mut exit_code as *mut _ as usize) {
-            -1 => break Err(()),
-            -2 => yield_(),
-            exit_pid => break Ok((exit_pid as Pid, exit_code)),
-        }
+// 只有失败时才会返回, envp和args一样以空指针结尾
+pub fn execve(path: &str, args: &[CStr], envp: &[CStr]) -> Errno {
+    match Errno::check(sys_exec(
+        path.as_ptr() as usize,
+        args.as_ptr() as usize,
+        envp.as_ptr() as usize,
+    )) {
+        Err(errno) => errno,
+        Ok(_) => panic!("unreachable after sys_exec!"),
     }
 }
 
-pub fn wait() -> (Pid, ExitCode) {
-    const ANY: isize = -1;
-    let mut exit_code: i32 = 0;
+const WAIT_ANY: Pid = -1isize as Pid;
+const WNOHANG: usize = 1;
+
+// 阻塞直到子进程退出, 被信号打断后会重新等待
+// SIGCHLD被忽略时子进程不会留下来等待回收, 全部退出后返回ECHILD
+pub fn waitpid(pid: Pid) -> Result<(Pid, ExitStatus)> {
+    let mut status: i32 = 0;
     loop {
-        match sys_waitpid(ANY as usize, &mut exit_code as *mut _ as usize) {
-            -2 => yield_(),
-            exit_pid => break (exit_pid as Pid, exit_code),
+        match Errno::check(sys_waitpid(pid, &mut status as *mut _ as usize, 0)) {
+            Err(Errno::EINTR) => continue,
+            Err(errno) => break Err(errno),
+            Ok(exit_pid) => break Ok((exit_pid as Pid, ExitStatus::from_raw(status))),
         }
     }
 }
 
+pub fn wait() -> Result<(Pid, ExitStatus)> {
+    waitpid(WAIT_ANY)
+}
+
+// 不阻塞, 子进程都还没有退出时返回None
+pub fn try_waitpid(pid: Pid) -> Result<Option<(Pid, ExitStatus)>> {
+    let mut status: i32 = 0;
+    match Errno::check(sys_waitpid(pid, &mut status as *mut _ as usize, WNOHANG))? {
+        0 => Ok(None),
+        exit_pid => Ok(Some((exit_pid as Pid, ExitStatus::from_raw(status)))),
+    }
+}
+
+pub fn try_wait() -> Result<Option<(Pid, ExitStatus)>> {
+    try_waitpid(WAIT_ANY)
+}
+
 pub fn getpid() -> Pid {
     sys_getpid() as Pid
 }
 
+const PRIO_PROCESS: usize = 0;
+
+// pid为0时表示当前进程, nice值越小优先级越高, 范围是[-20, 19]
+pub fn setpriority(pid: Pid, nice: isize) -> Result {
+    Errno::check(sys_setpriority(PRIO_PROCESS, pid, nice)).map(|_| ())
+}
+
+pub fn getpriority(pid: Pid) -> Result<isize> {
+    Errno::check(sys_getpriority(PRIO_PROCESS, pid)).map(|ret| 20 - ret as isize)
+}
+
+pub const CLK_TCK: usize = 100;
+
+// 返回系统启动以来的时钟滴答数和当前进程的用户态/内核态时间
+pub fn times() -> (usize, Tms) {
+    let mut tms = Tms::default();
+    let ticks = sys_times(&mut tms as *mut _ as usize) as usize;
+    (ticks, tms)
+}
+
+#[repr(isize)]
+pub enum RUsageWho {
+    // 当前进程
+    Current = 0,
+    // 已经被wait回收的子进程
+    Children = -1,
+}
+
+pub fn getrusage(who: RUsageWho) -> Result<RUsage> {
+    let mut usage = RUsage::default();
+    Errno::check(sys_getrusage(who as isize, &mut usage as *mut _ as usize))?;
+    Ok(usage)
+}
+
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ITimer {
+    // 按实际时间计时, 到期时发送SIGALRM
+    Real = 0,
+    // 只计用户态时间, 到期时发送SIGVTALRM
+    Virtual = 1,
+    // 计用户态和内核态时间, 到期时发送SIGPROF
+    Prof = 2,
+}
+
+pub fn getitimer(which: ITimer) -> ITimerVal {
+    let mut curr = ITimerVal::default();
+    sys_getitimer(which as usize, &mut curr as *mut _ as usize);
+    curr
+}
+
+// value为0时取消定时器, interval为0时只触发一次, 返回原来的设置
+pub fn setitimer(which: ITimer, new: ITimerVal) -> Result<ITimerVal> {
+    let mut old = ITimerVal::default();
+    Errno::check(sys_setitimer(
+        which as usize,
+        &new as *const _ as usize,
+        &mut old as *mut _ as usize,
+    ))?;
+    Ok(old)
+}
+
+// 和libc一样用ITIMER_REAL实现, seconds为0时取消
+// 返回上一个闹钟剩余的秒数, 四舍五入但不会把还没到期的闹钟报告成0
+pub fn alarm(seconds: usize) -> usize {
+    let new = ITimerVal {
+        interval: TimeVal::default(),
+        value: TimeVal {
+            sec: seconds,
+            usec: 0,
+        },
+    };
+    let old = setitimer(ITimer::Real, new).unwrap().value;
+    if old.usec >= 500_000 || (old.sec == 0 && old.usec > 0) {
+        old.sec + 1
+    } else {
+        old.sec
+    }
+}
+
 pub fn shutdown() -> ! {
-    sys_shutdown();
+    sys_shutdown(0);
+    unreachable!()
+}
+
+pub enum RebootType {
+    Cold = 0,
+    Warm = 1,
+}
+
+pub fn reboot(ty: RebootType) -> ! {
+    sys_reboot(ty as usize);
     unreachable!()
 }
 
+// 被信号打断时返回Err, rem中是剩余的时间
+pub fn nanosleep(req: &TimeSpec, rem: &mut TimeSpec) -> Result {
+    Errno::check(sys_nanosleep(
+        req as *const _ as usize,
+        rem as *mut _ as usize,
+    ))
+    .map(|_| ())
+}
+
+// 被信号打断后会继续睡完剩下的时间
 pub fn sleep(duration: Ms) {
-    let start = time();
-    while time() < start + duration {
-        yield_();
+    let mut req = TimeSpec::from_ms(duration);
+    let mut rem = TimeSpec::default();
+    while let Err(Errno::EINTR) = nanosleep(&req, &mut rem) {
+        req = rem;
     }
 }
@@ -1,8 +1,8 @@
 use bitflags::bitflags;
 
 use crate::{
-    syscall::{sys_kill, sys_sigaction, sys_sigret, sys_sysprocmask},
-    Pid, Result,
+    syscall::{sys_kill, sys_sigaction, sys_sigpending, sys_sigprocmask, sys_sigsuspend},
+    Errno, Pid, Result,
 };
 
 pub type Signal = i32;
@@ -84,42 +84,96 @@ impl Default for SignalFlags {
     }
 }
 
+// 处理函数的第二个参数
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SigInfo {
+    pub signo: i32,
+    pub errno: i32,
+    pub code: i32,
+    // SIGSEGV时是出错的地址
+    pub addr: usize,
+}
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct SaFlags: u32 {
+        // 只对SIGCHLD有效, 子进程退出后由内核直接回收, wait拿不到它们的状态
+        const NOCLDWAIT = 1 << 1;
+    }
+}
+
+// handler的两个特殊值
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+// 处理函数可以直接返回, 内核会把返回地址设为sigreturn跳板
+pub type SignalHandler = extern "C" fn(Signal);
+pub type SignalInfoHandler = extern "C" fn(Signal, &SigInfo);
+
 #[repr(C, align(16))]
 #[derive(Clone, Copy, Default)]
 pub struct SignalAction {
-    action: usize,
+    handler: usize,
+    // 处理函数执行期间额外屏蔽的信号, 信号本身总是被屏蔽
     mask: SignalFlags,
+    flags: SaFlags,
 }
 
 impl SignalAction {
-    pub fn new(action: fn() -> !, mask: SignalFlags) -> Self {
+    pub fn new(handler: SignalHandler, mask: SignalFlags) -> Self {
+        Self {
+            handler: handler as usize,
+            mask,
+            flags: SaFlags::empty(),
+        }
+    }
+
+    pub fn with_info(handler: SignalInfoHandler, mask: SignalFlags) -> Self {
         Self {
-            action: action as usize,
+            handler: handler as usize,
             mask,
+            flags: SaFlags::empty(),
         }
     }
 
     pub fn bare(mask: SignalFlags) -> Self {
-        Self { action: 0, mask }
+        Self {
+            handler: SIG_DFL,
+            mask,
+            flags: SaFlags::empty(),
+        }
+    }
+
+    // 丢弃信号, 对SIGCHLD来说还意味着子进程不留下僵尸进程
+    pub fn ignore() -> Self {
+        Self {
+            handler: SIG_IGN,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_flags(mut self, flags: SaFlags) -> Self {
+        self.flags = flags;
+        self
     }
 
     pub fn mask(&self) -> SignalFlags {
         self.mask
     }
 
-    pub fn action(&self) -> Option<fn() -> !> {
-        match self.action {
-            0 => None,
-            action => Some(unsafe { core::mem::transmute(action) }),
-        }
+    pub fn flags(&self) -> SaFlags {
+        self.flags
+    }
+
+    // SIG_DFL, SIG_IGN或者处理函数的地址
+    pub fn handler(&self) -> usize {
+        self.handler
     }
 }
 
 pub fn kill(pid: Pid, signal: Signal) -> Result {
-    match sys_kill(pid, signal as usize) {
-        -1 => Err(()),
-        _ => Ok(()),
-    }
+    Errno::check(sys_kill(pid, signal as usize)).map(|_| ())
 }
 
 pub fn sig_getaction(signal: Signal) -> SignalAction {
@@ -138,14 +192,57 @@ pub fn sig_setaction(signal: Signal, action: SignalAction) -> SignalAction {
     old
 }
 
-pub fn sig_procmask(mask: SignalFlags) -> Result<SignalFlags> {
-    match sys_sysprocmask(mask.bits() as usize) {
-        -1 => Err(()),
-        old => Ok(SignalFlags::from_bits_truncate(old as i32)),
-    }
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigHow {
+    // 屏蔽字加上set
+    Block = 0,
+    // 屏蔽字去掉set
+    Unblock = 1,
+    // 屏蔽字替换为set
+    SetMask = 2,
+}
+
+// 和内核交换的信号集是64位的
+type SigSet = u64;
+
+fn to_sigset(flags: SignalFlags) -> SigSet {
+    flags.bits() as u32 as SigSet
+}
+
+fn from_sigset(set: SigSet) -> SignalFlags {
+    SignalFlags::from_bits_truncate(set as u32 as i32)
+}
+
+// 修改屏蔽字并返回原来的屏蔽字, SIGKILL和SIGSTOP会被内核忽略
+pub fn sig_procmask(how: SigHow, set: SignalFlags) -> Result<SignalFlags> {
+    let set = to_sigset(set);
+    let mut old: SigSet = 0;
+    Errno::check(sys_sigprocmask(
+        how as usize,
+        &set as *const _ as usize,
+        &mut old as *mut _ as usize,
+    ))
+    .map(|_| from_sigset(old))
+}
+
+pub fn sig_getmask() -> SignalFlags {
+    let mut old: SigSet = 0;
+    sys_sigprocmask(0, 0, &mut old as *mut _ as usize);
+    from_sigset(old)
+}
+
+// 已经到达但被屏蔽的信号
+pub fn sig_pending() -> SignalFlags {
+    let mut set: SigSet = 0;
+    sys_sigpending(&mut set as *mut _ as usize);
+    from_sigset(set)
 }
 
-pub fn sig_ret() -> ! {
-    sys_sigret();
-    unreachable!();
+// 临时把屏蔽字换成mask并等待信号, 处理函数返回后恢复原来的屏蔽字
+pub fn sig_suspend(mask: SignalFlags) -> Errno {
+    let mask = to_sigset(mask);
+    Errno::check(sys_sigsuspend(&mask as *const _ as usize))
+        .err()
+        .unwrap_or(Errno::EINTR)
 }
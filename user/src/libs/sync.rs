@@ -0,0 +1,91 @@
+// 内核提供的同步原语, 只在同一个进程的线程之间有效
+// 进程正在退出或者收到信号时内核会提前返回EINTR, 这里会重新等待
+
+use crate::{
+    syscall::{
+        sys_condvar_create, sys_condvar_signal, sys_condvar_wait, sys_enable_deadlock_detect,
+        sys_mutex_create, sys_mutex_lock, sys_mutex_unlock, sys_semaphore_create,
+        sys_semaphore_down, sys_semaphore_up,
+    },
+    Errno, Result,
+};
+
+// 内核没有提供销毁的系统调用, id在进程exec之前一直有效, 所以可以随意复制
+#[derive(Clone, Copy)]
+pub struct Mutex(usize);
+
+impl Mutex {
+    // 拿不到锁时阻塞
+    pub fn new() -> Result<Self> {
+        Errno::check(sys_mutex_create(1)).map(Self)
+    }
+
+    // 拿不到锁时让出cpu
+    pub fn new_spin() -> Result<Self> {
+        Errno::check(sys_mutex_create(0)).map(Self)
+    }
+
+    // 开启死锁检测时, 会导致死锁的申请返回EDEADLK
+    pub fn lock(&self) -> Result {
+        loop {
+            match Errno::check(sys_mutex_lock(self.0)) {
+                Err(Errno::EINTR) => continue,
+                ret => break ret.map(|_| ()),
+            }
+        }
+    }
+
+    pub fn unlock(&self) -> Result {
+        Errno::check(sys_mutex_unlock(self.0)).map(|_| ())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Semaphore(usize);
+
+impl Semaphore {
+    pub fn new(count: usize) -> Result<Self> {
+        Errno::check(sys_semaphore_create(count)).map(Self)
+    }
+
+    pub fn up(&self) -> Result {
+        Errno::check(sys_semaphore_up(self.0)).map(|_| ())
+    }
+
+    // 开启死锁检测时, 会导致死锁的申请返回EDEADLK
+    pub fn down(&self) -> Result {
+        loop {
+            match Errno::check(sys_semaphore_down(self.0)) {
+                Err(Errno::EINTR) => continue,
+                ret => break ret.map(|_| ()),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Condvar(usize);
+
+impl Condvar {
+    pub fn new() -> Result<Self> {
+        Errno::check(sys_condvar_create()).map(Self)
+    }
+
+    pub fn signal(&self) -> Result {
+        Errno::check(sys_condvar_signal(self.0)).map(|_| ())
+    }
+
+    // 调用前必须持有mutex, 返回时重新持有mutex, 醒来后需要重新检查条件
+    pub fn wait(&self, mutex: &Mutex) -> Result {
+        match Errno::check(sys_condvar_wait(self.0, mutex.0)) {
+            //被打断时内核没能重新拿到锁
+            Err(Errno::EINTR) => mutex.lock(),
+            ret => ret.map(|_| ()),
+        }
+    }
+}
+
+// 对当前进程开启或关闭基于银行家算法的死锁检测
+pub fn enable_deadlock_detect(enabled: bool) -> Result {
+    Errno::check(sys_enable_deadlock_detect(enabled as usize)).map(|_| ())
+}
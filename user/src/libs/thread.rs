@@ -0,0 +1,51 @@
+use alloc::boxed::Box;
+
+use crate::{
+    syscall::{sys_exit, sys_gettid, sys_thread_create, sys_waittid},
+    Errno, ExitCode, Result, Tid,
+};
+
+type ThreadMain = Box<dyn FnOnce() -> ExitCode + Send + 'static>;
+
+// 新线程的入口, arg是thread_create中泄漏出来的闭包
+extern "C" fn thread_start(arg: usize) -> ! {
+    let f = unsafe { Box::from_raw(arg as *mut ThreadMain) };
+    thread_exit(f())
+}
+
+// 在当前进程中创建线程执行f, f的返回值是线程的退出码
+pub fn thread_create<F>(f: F) -> Result<Tid>
+where
+    F: FnOnce() -> ExitCode + Send + 'static,
+{
+    let arg = Box::into_raw(Box::new(Box::new(f) as ThreadMain));
+    match Errno::check(sys_thread_create(thread_start as usize, arg as usize)) {
+        Ok(tid) => Ok(tid as Tid),
+        Err(errno) => {
+            drop(unsafe { Box::from_raw(arg) });
+            Err(errno)
+        }
+    }
+}
+
+// 只退出当前线程, 最后一个线程退出时进程退出
+pub fn thread_exit(exit_code: ExitCode) -> ! {
+    sys_exit(exit_code as usize);
+    panic!("unreachable after sys_exit!");
+}
+
+pub fn gettid() -> Tid {
+    sys_gettid() as Tid
+}
+
+// 阻塞直到线程tid退出, 被信号打断后会重新等待
+pub fn waittid(tid: Tid) -> Result<ExitCode> {
+    let mut exit_code: ExitCode = 0;
+    loop {
+        match Errno::check(sys_waittid(tid, &mut exit_code as *mut _ as usize)) {
+            Err(Errno::EINTR) => continue,
+            Err(errno) => break Err(errno),
+            Ok(_) => break Ok(exit_code),
+        }
+    }
+}
@@ -1,7 +1,135 @@
+use core::fmt::{self, Display};
+
+use super::{errno::Errno, signal::Signal};
+
 pub type CStr = *const u8;
 pub type Fd = usize;
 pub type Ms = usize;
 pub type Pid = usize;
+pub type Tid = usize;
 pub type ExitCode = i32;
 pub type Argv = [&'static str];
-pub type Result<T = (), E = ()> = core::result::Result<T, E>;
+pub type Result<T = (), E = Errno> = core::result::Result<T, E>;
+
+// waitpid得到的子进程状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    // 退出码只保留低8位
+    Exited(ExitCode),
+    Signaled(Signal),
+}
+
+impl ExitStatus {
+    // 内核写回的状态字和linux相同
+    pub fn from_raw(status: i32) -> Self {
+        match status & 0x7f {
+            0 => Self::Exited((status >> 8) & 0xff),
+            signal => Self::Signaled(signal),
+        }
+    }
+
+    // shell的习惯: 被信号杀死时是128加上信号
+    pub fn code(self) -> ExitCode {
+        match self {
+            Self::Exited(code) => code,
+            Self::Signaled(signal) => 128 + signal,
+        }
+    }
+}
+
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(code) => write!(f, "exited with code {}", code),
+            Self::Signaled(signal) => write!(f, "killed by signal {}", signal),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+// 单位是字节
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct RLimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+// 和linux的struct rusage布局相同, 内核只统计了时间, 缺页次数和上下文切换次数
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RUsage {
+    pub utime: TimeVal,
+    pub stime: TimeVal,
+    pub maxrss: usize,
+    pub ixrss: usize,
+    pub idrss: usize,
+    pub isrss: usize,
+    pub minflt: usize,
+    pub majflt: usize,
+    pub nswap: usize,
+    pub inblock: usize,
+    pub oublock: usize,
+    pub msgsnd: usize,
+    pub msgrcv: usize,
+    pub nsignals: usize,
+    pub nvcsw: usize,
+    pub nivcsw: usize,
+}
+
+// 和linux的struct itimerval布局相同, value为0表示定时器没有启动
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ITimerVal {
+    pub interval: TimeVal,
+    pub value: TimeVal,
+}
+
+// 单位是1/CLK_TCK秒
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+impl TimeVal {
+    pub fn as_ms(&self) -> Ms {
+        self.sec * 1000 + self.usec / 1000
+    }
+
+    pub fn from_ms(ms: Ms) -> Self {
+        Self {
+            sec: ms / 1000,
+            usec: ms % 1000 * 1000,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.sec == 0 && self.usec == 0
+    }
+}
+
+impl TimeSpec {
+    pub fn from_ms(ms: Ms) -> Self {
+        Self {
+            sec: ms / 1000,
+            nsec: ms % 1000 * 1_000_000,
+        }
+    }
+}
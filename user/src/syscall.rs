@@ -8,17 +8,48 @@ pub const SYSCALL_SEEK: usize = 62;
 pub const SYSCALL_READ: usize = 63;
 pub const SYSCALL_WRITE: usize = 64;
 pub const SYSCALL_EXIT: usize = 93;
+pub const SYSCALL_EXIT_GROUP: usize = 94;
+pub const SYSCALL_FUTEX: usize = 98;
+pub const SYSCALL_NANOSLEEP: usize = 101;
+pub const SYSCALL_GETITIMER: usize = 102;
+pub const SYSCALL_SETITIMER: usize = 103;
 pub const SYSCALL_YIELD: usize = 124;
 pub const SYSCALL_KILL: usize = 129;
+pub const SYSCALL_SIGSUSPEND: usize = 133;
 pub const SYSCALL_SIGACTION: usize = 134;
 pub const SYSCALL_SIGPROCMASK: usize = 135;
+pub const SYSCALL_SIGPENDING: usize = 136;
 pub const SYSCALL_SIGRET: usize = 139;
+pub const SYSCALL_SETPRIORITY: usize = 140;
+pub const SYSCALL_GETPRIORITY: usize = 141;
+pub const SYSCALL_REBOOT: usize = 142;
+pub const SYSCALL_TIMES: usize = 153;
+pub const SYSCALL_GETRLIMIT: usize = 163;
+pub const SYSCALL_SETRLIMIT: usize = 164;
+pub const SYSCALL_GETRUSAGE: usize = 165;
 pub const SYSCALL_GET_TIME: usize = 169;
 pub const SYSCALL_GETPID: usize = 172;
 pub const SYSCALL_SBRK: usize = 214;
+pub const SYSCALL_MUNMAP: usize = 215;
 pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
+pub const SYSCALL_MMAP: usize = 222;
+pub const SYSCALL_MPROTECT: usize = 226;
 pub const SYSCALL_WAITPID: usize = 260;
+pub const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = 469;
+pub const SYSCALL_THREAD_CREATE: usize = 1000;
+pub const SYSCALL_GETTID: usize = 1001;
+pub const SYSCALL_WAITTID: usize = 1002;
+pub const SYSCALL_MUTEX_CREATE: usize = 1010;
+pub const SYSCALL_MUTEX_LOCK: usize = 1011;
+pub const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+pub const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
+pub const SYSCALL_SEMAPHORE_UP: usize = 1021;
+pub const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+pub const SYSCALL_CONDVAR_CREATE: usize = 1030;
+pub const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
+pub const SYSCALL_CONDVAR_WAIT: usize = 1032;
+pub const SYSCALL_SHUTDOWN: usize = 2000;
 
 pub fn syscall(id: usize, args: [usize; 3]) -> isize {
     let mut ret: isize;
@@ -34,6 +65,24 @@ pub fn syscall(id: usize, args: [usize; 3]) -> isize {
     ret
 }
 
+// 最多六个参数, 依次放在a0~a5中
+pub fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x14") args[4],
+            in("x15") args[5],
+            in("x17") id
+        );
+    }
+    ret
+}
+
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
@@ -58,10 +107,16 @@ pub fn sys_write(fd: usize, buffer: usize, len: usize) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer, len])
 }
 
+// 只退出当前线程
 pub fn sys_exit(exit_code: usize) -> isize {
     syscall(SYSCALL_EXIT, [exit_code, 0, 0])
 }
 
+// 退出整个进程
+pub fn sys_exit_group(exit_code: usize) -> isize {
+    syscall(SYSCALL_EXIT_GROUP, [exit_code, 0, 0])
+}
+
 pub fn sys_yield() -> isize {
     syscall(SYSCALL_YIELD, [0, 0, 0])
 }
@@ -74,8 +129,18 @@ pub fn sys_sigaction(signal: usize, action: usize, old_action: usize) -> isize {
     syscall(SYSCALL_SIGACTION, [signal, action, old_action])
 }
 
-pub fn sys_sysprocmask(mask: usize) -> isize {
-    syscall(SYSCALL_SIGPROCMASK, [mask, 0, 0])
+// set为NULL时只读取屏蔽字, oldset可以为NULL
+pub fn sys_sigprocmask(how: usize, set: usize, oldset: usize) -> isize {
+    syscall(SYSCALL_SIGPROCMASK, [how, set, oldset])
+}
+
+pub fn sys_sigpending(set: usize) -> isize {
+    syscall(SYSCALL_SIGPENDING, [set, 0, 0])
+}
+
+// 总是返回-EINTR
+pub fn sys_sigsuspend(mask: usize) -> isize {
+    syscall(SYSCALL_SIGSUSPEND, [mask, 0, 0])
 }
 
 pub fn sys_sigret() -> isize {
@@ -86,6 +151,11 @@ pub fn sys_gettime() -> isize {
     syscall(SYSCALL_GET_TIME, [0, 0, 0])
 }
 
+// 被信号打断时返回 -EINTR, 剩余时间写入 rem (rem 可以为 NULL)
+pub fn sys_nanosleep(req: usize, rem: usize) -> isize {
+    syscall(SYSCALL_NANOSLEEP, [req, rem, 0])
+}
+
 pub fn sys_sbrk(size: usize) -> isize {
     syscall(SYSCALL_SBRK, [size, 0, 0])
 }
@@ -94,26 +164,154 @@ pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
 
-pub fn sys_exec(path: usize, args: usize) -> isize {
-    syscall(SYSCALL_EXEC, [path, args, 0])
+pub fn sys_exec(path: usize, args: usize, envp: usize) -> isize {
+    syscall(SYSCALL_EXEC, [path, args, envp])
+}
+
+// 成功时返回映射的起始地址, 匿名映射忽略 fd 和 offset
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> isize {
+    syscall6(SYSCALL_MMAP, [addr, len, prot, flags, fd, offset])
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> isize {
+    syscall(SYSCALL_MUNMAP, [addr, len, 0])
+}
+
+// 范围中有没被映射的页时返回 -ENOMEM, 同时可写可执行时返回 -EACCES
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [addr, len, prot])
 }
 
 // exit_code == NULL 时不必保存
 // pid -1 时等待任意子进程退出
-// 返回 -1 要等待的子进程不存在
-//      -2 要等待的子进程未结束
-pub fn sys_waitpid(pid: usize, exit_code: usize) -> isize {
-    syscall(SYSCALL_WAITPID, [pid, exit_code, 0])
+// 要等待的子进程不存在时返回 -ECHILD
+// 子进程未结束时阻塞, 带上 WNOHANG 时立即返回 0
+pub fn sys_waitpid(pid: usize, exit_code: usize, options: usize) -> isize {
+    syscall(SYSCALL_WAITPID, [pid, exit_code, options])
+}
+
+// which 只支持 PRIO_PROCESS(0), who 为 0 表示当前进程
+pub fn sys_setpriority(which: usize, who: usize, nice: isize) -> isize {
+    syscall(SYSCALL_SETPRIORITY, [which, who, nice as usize])
+}
+
+// 成功时返回 20 - nice
+pub fn sys_getpriority(which: usize, who: usize) -> isize {
+    syscall(SYSCALL_GETPRIORITY, [which, who, 0])
 }
 
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+// FUTEX_WAIT: uaddr处的值等于val时阻塞, 超时返回-ETIMEDOUT, 值不相等返回-EAGAIN
+// FUTEX_WAKE: 唤醒最多val个阻塞在uaddr上的线程, 返回唤醒的个数
+// timeout为NULL时不会超时
+pub fn sys_futex(uaddr: usize, op: usize, val: usize, timeout: usize) -> isize {
+    syscall6(SYSCALL_FUTEX, [uaddr, op, val, timeout, 0, 0])
+}
+
+// 新线程从 entry 开始执行, arg 放在 a0 中, 返回新线程的 tid
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
+}
+
+pub fn sys_gettid() -> isize {
+    syscall(SYSCALL_GETTID, [0, 0, 0])
+}
+
+// 等待自己时返回 -EDEADLK, 线程不存在时返回 -ESRCH
+pub fn sys_waittid(tid: usize, exit_code: usize) -> isize {
+    syscall(SYSCALL_WAITTID, [tid, exit_code, 0])
+}
+
+// blocking 为 0 时创建自旋锁, 否则创建阻塞锁, 返回锁的 id
+pub fn sys_mutex_create(blocking: usize) -> isize {
+    syscall(SYSCALL_MUTEX_CREATE, [blocking, 0, 0])
+}
+
+// 开启死锁检测时, 会导致死锁的申请返回 -EDEADLK
+pub fn sys_mutex_lock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_LOCK, [id, 0, 0])
+}
+
+// 锁没有被持有时返回 -EPERM
+pub fn sys_mutex_unlock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_UNLOCK, [id, 0, 0])
+}
+
+pub fn sys_semaphore_create(count: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_CREATE, [count, 0, 0])
+}
+
+pub fn sys_semaphore_up(id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_UP, [id, 0, 0])
+}
+
+// 开启死锁检测时, 会导致死锁的申请返回 -EDEADLK
+pub fn sys_semaphore_down(id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DOWN, [id, 0, 0])
+}
+
+pub fn sys_condvar_create() -> isize {
+    syscall(SYSCALL_CONDVAR_CREATE, [0, 0, 0])
+}
+
+pub fn sys_condvar_signal(id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_SIGNAL, [id, 0, 0])
+}
+
+// 释放 mutex 后睡眠, 返回前重新获取 mutex
+pub fn sys_condvar_wait(id: usize, mutex: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_WAIT, [id, mutex, 0])
+}
+
+// enabled 只能是 0 或 1
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    syscall(SYSCALL_ENABLE_DEADLOCK_DETECT, [enabled, 0, 0])
+}
+
 pub fn sys_pipe(pipe: usize) -> isize {
     syscall(SYSCALL_PIPE, [pipe, 0, 0])
 }
 
-pub fn sys_shutdown() -> isize {
-    syscall(usize::MAX, [0, 0, 0])
+pub fn sys_shutdown(failure: usize) -> isize {
+    syscall(SYSCALL_SHUTDOWN, [failure, 0, 0])
+}
+
+pub fn sys_reboot(warm: usize) -> isize {
+    syscall(SYSCALL_REBOOT, [warm, 0, 0])
+}
+
+// 目前 resource 只支持 RLIMIT_STACK
+pub fn sys_getrlimit(resource: usize, rlim: usize) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, rlim, 0])
+}
+
+pub fn sys_setrlimit(resource: usize, rlim: usize) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, rlim, 0])
+}
+
+pub fn sys_times(buf: usize) -> isize {
+    syscall(SYSCALL_TIMES, [buf, 0, 0])
+}
+
+pub fn sys_getrusage(who: isize, usage: usize) -> isize {
+    syscall(SYSCALL_GETRUSAGE, [who as usize, usage, 0])
+}
+
+pub fn sys_getitimer(which: usize, curr: usize) -> isize {
+    syscall(SYSCALL_GETITIMER, [which, curr, 0])
+}
+
+// old可以为NULL
+pub fn sys_setitimer(which: usize, new: usize, old: usize) -> isize {
+    syscall(SYSCALL_SETITIMER, [which, new, old])
 }
@@ -80,12 +80,13 @@ fn main() {
             let vnode = root.create(&app).unwrap();
             vnode.write(0, &data);
         });
+    //没有js解释器, 用cat"执行"脚本, 在ysh中直接输入hello.js就能看到脚本内容
     let vnode = root.create("hello.js").unwrap();
     vnode.write(
         0,
-        br#"
+        br#"#!cat
 console.log('hello world')
-                "#,
+"#,
     );
     for entry in root.ls() {
         let name = entry.name();